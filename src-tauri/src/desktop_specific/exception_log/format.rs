@@ -0,0 +1,171 @@
+use std::fs;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use super::riscv::register_index;
+use super::CrashKind;
+
+/// 一种寄存器组的打印格式
+///
+/// `flags` 为标题行需要同时包含的标志；`registers` 为按打印顺序排列的寄存器，
+/// 可以写 ABI 名称（`ra`、`s0`/`fp`）或编号（`x1`），没有列出的寄存器视为没有打印。
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DumpLayout {
+    pub kind: CrashKind,
+    pub flags: Vec<String>,
+    pub registers: Vec<String>,
+}
+
+/// 标题行中各字段的关键字，按顺序查找，取第一个找到的
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HeaderKeys {
+    #[serde(default)]
+    pub err: Vec<String>,
+    #[serde(default)]
+    pub epc: Vec<String>,
+    #[serde(default)]
+    pub mcause: Vec<String>,
+    #[serde(default)]
+    pub mtval: Vec<String>,
+}
+
+impl Default for HeaderKeys {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+        Self {
+            err: keys(&["ERR:"]),
+            epc: keys(&["EPC:"]),
+            mcause: keys(&["MCAUSE:", "CAUSE:"]),
+            mtval: keys(&["MTVAL:", "TVAL:"]),
+        }
+    }
+}
+
+/// 崩溃转储格式描述
+///
+/// `layouts` 按顺序匹配标题行，先匹配到的生效；`fields` 省略时与 bluetrum 相同。
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CrashFormat {
+    pub name: String,
+    pub layouts: Vec<DumpLayout>,
+    #[serde(default)]
+    pub fields: HeaderKeys,
+}
+
+/// 内置的崩溃转储格式，第一个为默认格式
+pub fn builtin_formats() -> Vec<CrashFormat> {
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    vec![
+        // ERR: 0x5, EPC: 0x00401106
+        // x0~x31 共 32 个寄存器
+        // WDT_RST: 0x1
+        // 不打印 x0、x2~x3、x18~x27，共 19 个寄存器
+        CrashFormat {
+            name: "bluetrum_riscv".to_string(),
+            layouts: vec![
+                DumpLayout {
+                    kind: CrashKind::Exception,
+                    flags: names(&["ERR:", "EPC:"]),
+                    registers: (0..32).map(|i| format!("x{}", i)).collect(),
+                },
+                DumpLayout {
+                    kind: CrashKind::Watchdog,
+                    flags: names(&["WDT_RST:"]),
+                    registers: names(&[
+                        "ra", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+                        "a5", "a6", "a7", "t3", "t4", "t5", "t6",
+                    ]),
+                },
+            ],
+            fields: HeaderKeys::default(),
+        },
+    ]
+}
+
+/// 编译后的寄存器组格式
+pub struct CompiledLayout {
+    pub kind: CrashKind,
+    flags: Vec<String>,
+    pub order: Vec<usize>, // 按打印顺序排列的寄存器编号
+}
+
+/// 编译后的崩溃转储格式
+pub struct CompiledFormat {
+    layouts: Vec<CompiledLayout>,
+    pub fields: HeaderKeys,
+}
+
+impl CompiledFormat {
+    pub fn new(format: &CrashFormat) -> io::Result<Self> {
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid crash format {}: {}", format.name, message),
+            )
+        };
+
+        let mut layouts = Vec::new();
+        for layout in &format.layouts {
+            if layout.flags.iter().all(|flag| flag.is_empty()) {
+                return Err(invalid("layout without flags".to_string()));
+            }
+            let mut order = Vec::new();
+            for name in &layout.registers {
+                let index = register_index(name)
+                    .ok_or_else(|| invalid(format!("unknown register {}", name)))?;
+                if order.contains(&index) {
+                    return Err(invalid(format!("duplicate register {}", name)));
+                }
+                order.push(index);
+            }
+            layouts.push(CompiledLayout {
+                kind: layout.kind,
+                flags: layout.flags.clone(),
+                order,
+            });
+        }
+        if layouts.is_empty() {
+            return Err(invalid("no layouts".to_string()));
+        }
+
+        Ok(Self {
+            layouts,
+            fields: format.fields.clone(),
+        })
+    }
+
+    /// 按名称查找内置格式，名称为空时使用默认格式
+    pub fn builtin(name: Option<&str>) -> io::Result<Self> {
+        let formats = builtin_formats();
+        let format = match name.filter(|n| !n.is_empty()) {
+            None => &formats[0],
+            Some(name) => formats.iter().find(|f| f.name == name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown crash format {}", name),
+                )
+            })?,
+        };
+        Self::new(format)
+    }
+
+    /// 从 JSON 配置文件读取格式
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let format: CrashFormat = serde_json::from_str(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid crash format file {}: {}", path, e),
+            )
+        })?;
+        Self::new(&format)
+    }
+
+    /// 查找与标题行匹配的寄存器组格式
+    pub fn match_header(&self, line: &str) -> Option<&CompiledLayout> {
+        self.layouts
+            .iter()
+            .find(|layout| layout.flags.iter().all(|flag| line.contains(flag.as_str())))
+    }
+}
@@ -0,0 +1,283 @@
+mod format;
+mod riscv;
+mod stack;
+mod symbolize;
+mod unwind;
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::process_ascii_lines_from_file;
+use format::{builtin_formats, CompiledFormat, CompiledLayout, CrashFormat, HeaderKeys};
+use riscv::{describe_mcause, REG_NAMES, REG_RA, REG_SP};
+use stack::{parse_stack_flag, parse_stack_line, StackDump};
+use symbolize::{RegSymbol, Symbolizer};
+use unwind::{backtrace, BacktraceFrame};
+
+// 格式中没有打印的寄存器（例如看门狗复位时的 x0）
+const EMPTY_REG: &str = "0xXXXXXXXX";
+
+// 崩溃类型
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashKind {
+    Exception, // 异常，例如 ERR/EPC
+    Watchdog,  // 看门狗复位，例如 WDT_RST
+}
+
+// 标题行中的字段，没有打印的字段为 None
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CrashHeader {
+    err: Option<u32>,      // ERR 错误码
+    epc: Option<u32>,      // 发生异常的指令地址
+    mcause: Option<u32>,   // 异常原因，没有单独打印时取 ERR 错误码
+    mtval: Option<u32>,    // 异常附加信息（出错的地址或指令）
+    cause: Option<String>, // mcause 的说明
+}
+
+impl CrashHeader {
+    fn parse(header: &str, keys: &HeaderKeys) -> Self {
+        let value = |keys: &[String]| keys.iter().find_map(|key| header_value(header, key));
+        let err = value(&keys.err);
+        let mcause = value(&keys.mcause).or(err);
+        Self {
+            err,
+            epc: value(&keys.epc),
+            mcause,
+            mtval: value(&keys.mtval),
+            cause: mcause.and_then(describe_mcause).map(str::to_string),
+        }
+    }
+}
+
+// 一个寄存器
+#[derive(Serialize, Debug, Clone)]
+pub struct Register {
+    name: &'static str, // ABI 名称
+    value: String,      // 寄存器的值，没有打印的为 0xXXXXXXXX，没有读到的为空
+}
+
+impl Register {
+    fn value(&self) -> Option<u32> {
+        u32::from_str_radix(self.value.strip_prefix("0x")?, 16).ok()
+    }
+}
+
+// 日志中的一次崩溃
+#[derive(Serialize, Debug, Clone)]
+pub struct CrashRecord {
+    line: usize,                    // 标题行的行号，从 1 开始
+    kind: CrashKind,                // 崩溃类型
+    header: String,                 // 寄存器组的标题
+    fields: CrashHeader,            // 标题行中的字段
+    regs: Vec<Register>,            // x0~x31
+    symbols: Vec<RegSymbol>,        // EPC、RA 等代码地址的符号化结果，指定了 ELF 时才有
+    stack: Option<StackDump>,       // 寄存器组之后打印的栈数据
+    backtrace: Vec<BacktraceFrame>, // 从 EPC、RA、SP 开始的回溯
+}
+
+impl CrashRecord {
+    fn new(line: usize, layout: &CompiledLayout, header: &str, keys: &HeaderKeys) -> Self {
+        let fields = match layout.kind {
+            CrashKind::Exception => CrashHeader::parse(header, keys),
+            CrashKind::Watchdog => CrashHeader::default(),
+        };
+        Self {
+            line,
+            kind: layout.kind,
+            header: header.to_string(),
+            fields,
+            regs: REG_NAMES
+                .iter()
+                .enumerate()
+                .map(|(index, &name)| Register {
+                    name,
+                    value: if layout.order.contains(&index) {
+                        String::new()
+                    } else {
+                        EMPTY_REG.to_string()
+                    },
+                })
+                .collect(),
+            symbols: Vec::new(),
+            stack: None,
+            backtrace: Vec::new(),
+        }
+    }
+}
+
+/// 解析异常日志中的每一次崩溃，按在日志中出现的顺序返回
+///
+/// 指定固件 ELF 时，EPC、RA 以及其他落在代码段内的寄存器值会解析为函数名、源文件和行号。
+/// 寄存器组之后打印了栈数据时，从 EPC、RA、SP 开始回溯调用栈。
+/// 日志格式取自 `format_path` 指定的 JSON 配置文件，未指定时使用名为 `format` 的内置格式。
+#[tauri::command(async)]
+pub fn process_exception_log(
+    file_path: &str,
+    elf_path: Option<String>,
+    format: Option<String>,
+    format_path: Option<String>,
+) -> Result<Vec<CrashRecord>, String> {
+    let format = match format_path.filter(|p| !p.is_empty()) {
+        Some(format_path) => CompiledFormat::load(&format_path),
+        None => CompiledFormat::builtin(format.as_deref()),
+    }
+    .map_err(|e| e.to_string())?;
+    let mut crashes = parse_crashes(file_path, &format).map_err(|e| e.to_string())?;
+    let symbolizer = match elf_path.filter(|p| !p.is_empty()) {
+        Some(elf_path) => Some(Symbolizer::open(&elf_path).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    for crash in &mut crashes {
+        if let Some(symbolizer) = &symbolizer {
+            crash.symbols = symbolize_regs(crash, symbolizer);
+        }
+        let regs: [Option<u32>; 32] = std::array::from_fn(|i| crash.regs[i].value());
+        crash.backtrace = backtrace(
+            crash.fields.epc,
+            &regs,
+            crash.stack.as_ref(),
+            symbolizer.as_ref(),
+        );
+    }
+    Ok(crashes)
+}
+
+/// 内置的崩溃转储格式，可以作为编写配置文件的模板
+#[tauri::command]
+pub fn exception_log_formats() -> Vec<CrashFormat> {
+    builtin_formats()
+}
+
+// 正在读取的部分
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Regs,      // 寄存器组
+    AfterRegs, // 寄存器组已结束，之后可能是栈数据
+    Stack,     // 栈数据
+}
+
+fn parse_crashes(file_path: &str, format: &CompiledFormat) -> io::Result<Vec<CrashRecord>> {
+    let mut crashes = Vec::new();
+    let mut current: Option<(CrashRecord, &CompiledLayout)> = None; // 正在读取的崩溃及其格式
+    let mut section = Section::Regs;
+    let mut index = 0;
+    let mut line_number = 0;
+    process_ascii_lines_from_file(file_path, |line| {
+        line_number += 1;
+
+        if let Some(layout) = format.match_header(&line) {
+            // 上一次崩溃的寄存器没有打印完整，保留已读到的部分
+            crashes.extend(current.take().map(|(crash, _)| crash));
+            let crash = CrashRecord::new(line_number, layout, &line, &format.fields);
+            current = Some((crash, layout));
+            section = Section::Regs;
+            index = 0;
+        } else if let Some((crash, layout)) = current.as_mut() {
+            let ended = match section {
+                // 栈数据的标志行可能紧跟在没有打印完整的寄存器组之后
+                Section::Regs | Section::AfterRegs if start_stack(crash, &line) => {
+                    section = Section::Stack;
+                    false
+                }
+                Section::Regs => {
+                    if parse_reg_line(crash, &layout.order, &mut index, &line) {
+                        section = Section::AfterRegs;
+                    }
+                    false
+                }
+                Section::AfterRegs => true,
+                Section::Stack => !crash.stack.as_mut().is_some_and(|s| s.push_line(&line)),
+            };
+            if ended {
+                crashes.extend(current.take().map(|(crash, _)| crash));
+            }
+        }
+        true
+    })?;
+    crashes.extend(current.map(|(crash, _)| crash));
+
+    Ok(crashes)
+}
+
+// 栈数据的标志行（例如 "STACK: 0x20000800"），或不带标志、直接以 "地址: 字 字 ..." 开始的栈数据
+// 没有给出起始地址时从 SP 开始
+fn start_stack(crash: &mut CrashRecord, line: &str) -> bool {
+    let sp = crash.regs[REG_SP].value().unwrap_or(0);
+    if let Some(address) = parse_stack_flag(line) {
+        crash.stack = Some(StackDump::new(address.unwrap_or(sp)));
+        return true;
+    }
+    if let Some((Some(_), _)) = parse_stack_line(line) {
+        let mut stack = StackDump::new(sp);
+        stack.push_line(line);
+        crash.stack = Some(stack);
+        return true;
+    }
+    false
+}
+
+// 解析一行寄存器，返回寄存器组是否已经结束
+// `order` 为按打印顺序排列的寄存器编号，`index` 为已读到的寄存器个数
+fn parse_reg_line(crash: &mut CrashRecord, order: &[usize], index: &mut usize, line: &str) -> bool {
+    let mut ended = false;
+    for l in line.split(' ') {
+        if l.is_empty() {
+            continue;
+        }
+        let Some(&reg_index) = order.get(*index) else {
+            return true;
+        };
+
+        if let Ok(reg) = u32::from_str_radix(l, 16) {
+            crash.regs[reg_index].value = format!("{:#010X}", reg);
+        } else {
+            // 不是寄存器的内容，寄存器组结束
+            ended = true;
+        }
+
+        *index += 1;
+    }
+
+    ended || *index >= order.len()
+}
+
+// 符号化 EPC（取自标题行）、RA，以及其他值落在代码段内的寄存器
+fn symbolize_regs(crash: &CrashRecord, symbolizer: &Symbolizer) -> Vec<RegSymbol> {
+    let mut symbols = Vec::new();
+    let mut push = |register: &str, address: u32, return_address: bool| {
+        symbols.push(RegSymbol {
+            register: register.to_string(),
+            address: format!("{:#010X}", address),
+            frames: symbolizer.symbolize(address as u64, return_address),
+        });
+    };
+
+    if let Some(epc) = crash.fields.epc {
+        push("epc", epc, false);
+    }
+    for (index, reg) in crash.regs.iter().enumerate() {
+        let Some(value) = reg.value() else {
+            continue;
+        };
+        // RA 总是解析，其他寄存器只解析看起来像代码地址的值
+        if index == REG_RA || (index != 0 && symbolizer.is_code(value as u64)) {
+            push(reg.name, value, index == REG_RA);
+        }
+    }
+    symbols
+}
+
+// 取出标题行中 `key` 之后的十六进制数，例如 "EPC: 0x1234" 或 "EPC:1234"
+fn header_value(header: &str, key: &str) -> Option<u32> {
+    let value = header[header.find(key)? + key.len()..].trim_start();
+    let value = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    let end = value
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(value.len());
+    u32::from_str_radix(&value[..end], 16).ok()
+}
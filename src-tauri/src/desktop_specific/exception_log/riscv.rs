@@ -0,0 +1,58 @@
+// RISC-V 通用寄存器的 ABI 名称，按 x0~x31 排列
+pub const REG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+pub const REG_RA: usize = 1;
+pub const REG_SP: usize = 2;
+
+/// 按 ABI 名称（s0 也可以写作 fp）或编号（x0~x31）查找寄存器，不区分大小写
+pub fn register_index(name: &str) -> Option<usize> {
+    let name = name.trim().to_ascii_lowercase();
+    if name == "fp" {
+        return Some(8);
+    }
+    if let Some(index) = REG_NAMES.iter().position(|&n| n == name) {
+        return Some(index);
+    }
+    let index: usize = name.strip_prefix('x')?.parse().ok()?;
+    (index < REG_NAMES.len()).then_some(index)
+}
+
+// mcause 最高位为 1 时是中断，否则是异常
+const MCAUSE_INTERRUPT: u32 = 1 << 31;
+
+/// 解释 mcause，未定义的原因返回 None
+pub fn describe_mcause(mcause: u32) -> Option<&'static str> {
+    let code = mcause & !MCAUSE_INTERRUPT;
+    if mcause & MCAUSE_INTERRUPT != 0 {
+        return Some(match code {
+            1 => "监管模式软件中断 (Supervisor software interrupt)",
+            3 => "机器模式软件中断 (Machine software interrupt)",
+            5 => "监管模式定时器中断 (Supervisor timer interrupt)",
+            7 => "机器模式定时器中断 (Machine timer interrupt)",
+            9 => "监管模式外部中断 (Supervisor external interrupt)",
+            11 => "机器模式外部中断 (Machine external interrupt)",
+            _ => return None,
+        });
+    }
+    Some(match code {
+        0 => "取指地址未对齐 (Instruction address misaligned)",
+        1 => "取指访问错误 (Instruction access fault)",
+        2 => "非法指令 (Illegal instruction)",
+        3 => "断点 (Breakpoint)",
+        4 => "读地址未对齐 (Load address misaligned)",
+        5 => "读访问错误 (Load access fault)",
+        6 => "写地址未对齐 (Store/AMO address misaligned)",
+        7 => "写访问错误 (Store/AMO access fault)",
+        8 => "用户模式环境调用 (Environment call from U-mode)",
+        9 => "监管模式环境调用 (Environment call from S-mode)",
+        11 => "机器模式环境调用 (Environment call from M-mode)",
+        12 => "取指页错误 (Instruction page fault)",
+        13 => "读页错误 (Load page fault)",
+        15 => "写页错误 (Store/AMO page fault)",
+        _ => return None,
+    })
+}
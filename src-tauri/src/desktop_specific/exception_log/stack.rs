@@ -0,0 +1,109 @@
+use serde::Serialize;
+
+// 栈数据开始的标志，须为行首的单词且之后带冒号，不区分大小写，例如 "STACK:"、"Stack dump:"
+const STACK_FLAG: &str = "stack";
+
+/// 寄存器组之后打印的栈数据
+#[derive(Serialize, Debug, Clone)]
+pub struct StackDump {
+    pub start: u32,      // 第一个字的地址
+    pub words: Vec<u32>, // 按地址递增排列的字
+}
+
+impl StackDump {
+    pub fn new(start: u32) -> Self {
+        Self {
+            start,
+            words: Vec::new(),
+        }
+    }
+
+    // 最后一个字之后的地址
+    pub fn end(&self) -> u32 {
+        self.start.wrapping_add(self.words.len() as u32 * 4)
+    }
+
+    pub fn contains(&self, address: u32) -> bool {
+        address >= self.start && address < self.end()
+    }
+
+    /// 读取地址处的字，不在栈数据范围内或没有按字对齐时返回 None
+    pub fn read(&self, address: u32) -> Option<u32> {
+        let offset = address.checked_sub(self.start)?;
+        if offset % 4 != 0 {
+            return None;
+        }
+        self.words.get(offset as usize / 4).copied()
+    }
+
+    /// 追加一行栈数据，返回这一行是否为栈数据
+    ///
+    /// 行的格式为 "[地址:] 字 字 ..."，带地址时地址必须与已读到的数据连续。
+    pub fn push_line(&mut self, line: &str) -> bool {
+        let Some((address, words)) = parse_stack_line(line) else {
+            return false;
+        };
+        if let Some(address) = address {
+            if self.words.is_empty() {
+                self.start = address;
+            } else if address != self.end() {
+                return false;
+            }
+        }
+        self.words.extend(words);
+        true
+    }
+}
+
+/// 是否为栈数据开始的标志行，返回标志行中给出的起始地址
+pub fn parse_stack_flag(line: &str) -> Option<Option<u32>> {
+    // 寄存器组中间的 "stack overflow" 之类的提示不是标志行
+    let rest = line.trim_start();
+    if !rest.get(..STACK_FLAG.len())?.eq_ignore_ascii_case(STACK_FLAG) {
+        return None;
+    }
+    let rest = &rest[STACK_FLAG.len()..];
+    if rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let rest = &rest[rest.find(':')? + 1..];
+    // 例如 "STACK: 0x20000800"
+    let address = rest.find("0x").and_then(|i| {
+        let digits = &rest[i + 2..];
+        let end = digits
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(digits.len());
+        parse_hex(&digits[..end])
+    });
+    Some(address)
+}
+
+/// 解析 "[地址:] 字 字 ..." 格式的行，不是这种格式时返回 None
+pub fn parse_stack_line(line: &str) -> Option<(Option<u32>, Vec<u32>)> {
+    let mut tokens = line.split_whitespace().peekable();
+    let address = match tokens.peek() {
+        Some(token) if token.ends_with(':') => {
+            let address = parse_hex(token.trim_end_matches(':'))?;
+            tokens.next();
+            Some(address)
+        }
+        _ => None,
+    };
+    let words = tokens.map(parse_hex).collect::<Option<Vec<u32>>>()?;
+    if words.is_empty() {
+        return None;
+    }
+    Some((address, words))
+}
+
+// 解析十六进制数，可以带 0x 前缀，其后不能有其他字符
+fn parse_hex(token: &str) -> Option<u32> {
+    let token = token
+        .strip_prefix("0x")
+        .or_else(|| token.strip_prefix("0X"))
+        .unwrap_or(token);
+    if token.is_empty() || token.len() > 8 {
+        return None;
+    }
+    u32::from_str_radix(token, 16).ok()
+}
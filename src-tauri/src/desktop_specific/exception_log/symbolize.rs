@@ -0,0 +1,115 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+
+use addr2line::Loader;
+use object::{Object, ObjectSection, SectionKind};
+use serde::Serialize;
+
+use super::unwind::CfiTable;
+
+// 地址对应的一层函数，内联函数在前，最外层的函数在最后
+#[derive(Serialize, Debug, Clone)]
+pub struct SymbolFrame {
+    pub function: Option<String>, // 函数名，没有调试信息时取符号表
+    pub file: Option<String>,     // 源文件
+    pub line: Option<u32>,        // 行号
+}
+
+// 一个寄存器值的符号化结果
+#[derive(Serialize, Debug, Clone)]
+pub struct RegSymbol {
+    pub register: String,         // 寄存器名称
+    pub address: String,          // 寄存器的值
+    pub frames: Vec<SymbolFrame>, // 符号化结果
+}
+
+/// 使用固件 ELF 的符号表和 DWARF 行号信息解析地址，相当于 `addr2line -f -i`
+pub struct Symbolizer {
+    loader: Loader,
+    code_ranges: Vec<Range<u64>>, // 代码段的地址范围
+    cfi: Option<CfiTable>,        // 调用帧信息，用于回溯
+}
+
+impl Symbolizer {
+    pub fn open(elf_path: &str) -> io::Result<Self> {
+        let data = fs::read(elf_path)?;
+        let file = object::File::parse(&*data).map_err(|e| invalid_elf(elf_path, e))?;
+        let code_ranges = file
+            .sections()
+            .filter(|section| section.kind() == SectionKind::Text && section.size() > 0)
+            .map(|section| section.address()..section.address() + section.size())
+            .collect();
+        let cfi = CfiTable::load(&file);
+
+        let loader = Loader::new(elf_path).map_err(|e| invalid_elf(elf_path, e))?;
+        Ok(Self {
+            loader,
+            code_ranges,
+            cfi,
+        })
+    }
+
+    /// 地址是否位于代码段，用于挑出看起来像代码地址的寄存器值
+    pub fn is_code(&self, address: u64) -> bool {
+        self.code_ranges
+            .iter()
+            .any(|range| range.contains(&address))
+    }
+
+    pub fn cfi(&self) -> Option<&CfiTable> {
+        self.cfi.as_ref()
+    }
+
+    /// 解析一个地址，`return_address` 为返回地址（例如 RA）时按调用指令所在位置查找
+    pub fn symbolize(&self, address: u64, return_address: bool) -> Vec<SymbolFrame> {
+        // 返回地址指向调用指令的下一条指令，可能已经属于另一行甚至另一个函数
+        let probe = if return_address {
+            address.saturating_sub(1)
+        } else {
+            address
+        };
+
+        let mut frames = Vec::new();
+        if let Ok(mut iter) = self.loader.find_frames(probe) {
+            while let Ok(Some(frame)) = iter.next() {
+                let function = frame
+                    .function
+                    .as_ref()
+                    .and_then(|name| name.demangle().ok())
+                    .map(|name| name.into_owned());
+                let (file, line) = match &frame.location {
+                    Some(location) => (location.file.map(str::to_string), location.line),
+                    None => (None, None),
+                };
+                frames.push(SymbolFrame {
+                    function,
+                    file,
+                    line,
+                });
+            }
+        }
+
+        // 没有调试信息时只能从符号表得到函数名
+        let symbol = self.loader.find_symbol(probe);
+        match frames.last_mut() {
+            Some(frame) if frame.function.is_none() => {
+                frame.function = symbol.map(str::to_string);
+            }
+            None if symbol.is_some() => frames.push(SymbolFrame {
+                function: symbol.map(str::to_string),
+                file: None,
+                line: None,
+            }),
+            _ => {}
+        }
+        frames
+    }
+}
+
+fn invalid_elf(elf_path: &str, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Failed to load ELF {}: {}", elf_path, e),
+    )
+}
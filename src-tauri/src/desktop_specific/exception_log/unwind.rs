@@ -0,0 +1,219 @@
+use addr2line::gimli::{
+    BaseAddresses, CfaRule, DebugFrame, EhFrame, EndianSlice, Register, RegisterRule,
+    RunTimeEndian, UnwindContext, UnwindSection,
+};
+use object::{Object, ObjectSection};
+use serde::Serialize;
+
+use super::riscv::{REG_RA, REG_SP};
+use super::stack::StackDump;
+use super::symbolize::{SymbolFrame, Symbolizer};
+
+// 回溯的最大层数，防止栈数据异常时无限展开
+const MAX_FRAMES: usize = 32;
+
+// 回溯中一帧的来源
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnwindMethod {
+    Epc,  // 发生异常的指令
+    Ra,   // RA 寄存器
+    Cfi,  // 按 ELF 中的调用帧信息展开
+    Scan, // 扫描栈数据中看起来像返回地址的值
+}
+
+// 回溯中的一帧
+#[derive(Serialize, Debug, Clone)]
+pub struct BacktraceFrame {
+    pub address: String,               // 指令地址或返回地址
+    pub method: UnwindMethod,          // 来源
+    pub stack_address: Option<String>, // 扫描栈数据时，返回地址所在的栈地址
+    pub frames: Vec<SymbolFrame>,      // 符号化结果，指定了 ELF 时才有
+}
+
+/// ELF 中的调用帧信息（.debug_frame 或 .eh_frame）
+pub struct CfiTable {
+    data: Vec<u8>,
+    eh_frame: Option<u64>, // 为 .eh_frame 时的段地址
+    endian: RunTimeEndian,
+    address_size: u8,
+}
+
+impl CfiTable {
+    pub fn load(file: &object::File) -> Option<Self> {
+        let endian = if file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        let address_size = if file.is_64() { 8 } else { 4 };
+        for name in [".debug_frame", ".eh_frame"] {
+            let Some(section) = file.section_by_name(name) else {
+                continue;
+            };
+            let Ok(data) = section.uncompressed_data() else {
+                continue;
+            };
+            if data.is_empty() {
+                continue;
+            }
+            return Some(Self {
+                data: data.into_owned(),
+                eh_frame: (name == ".eh_frame").then(|| section.address()),
+                endian,
+                address_size,
+            });
+        }
+        None
+    }
+
+    /// 展开一层，返回调用者的寄存器和返回地址
+    ///
+    /// `return_address` 为 true 时 `pc` 是返回地址，按调用指令所在的位置查找。
+    fn step<F: Fn(u32) -> Option<u32>>(
+        &self,
+        pc: u32,
+        return_address: bool,
+        regs: &[Option<u32>; 32],
+        read: F,
+    ) -> Option<([Option<u32>; 32], u32)> {
+        let probe = if return_address { pc - 1 } else { pc } as u64;
+        let data = EndianSlice::new(&self.data, self.endian);
+        match self.eh_frame {
+            Some(address) => {
+                let section = EhFrame::from(data);
+                let bases = BaseAddresses::default().set_eh_frame(address);
+                step_section(&section, &bases, probe, regs, read)
+            }
+            None => {
+                let mut section = DebugFrame::from(data);
+                section.set_address_size(self.address_size);
+                step_section(&section, &BaseAddresses::default(), probe, regs, read)
+            }
+        }
+    }
+}
+
+fn step_section<'a, S, F>(
+    section: &S,
+    bases: &BaseAddresses,
+    probe: u64,
+    regs: &[Option<u32>; 32],
+    read: F,
+) -> Option<([Option<u32>; 32], u32)>
+where
+    S: UnwindSection<EndianSlice<'a, RunTimeEndian>>,
+    F: Fn(u32) -> Option<u32>,
+{
+    let fde = section
+        .fde_for_address(bases, probe, S::cie_from_offset)
+        .ok()?;
+    let ra_column = fde.cie().return_address_register();
+    let mut ctx = UnwindContext::new();
+    let row = fde
+        .unwind_info_for_address(section, bases, &mut ctx, probe)
+        .ok()?;
+
+    let cfa = match row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => {
+            let base = (*regs.get(register.0 as usize)?)?;
+            (base as i64 + offset) as u32
+        }
+        CfaRule::Expression(_) => return None,
+    };
+
+    // 没有规则的寄存器视为没有被修改
+    let mut caller = *regs;
+    for (index, value) in caller.iter_mut().enumerate() {
+        *value = match row.register(Register(index as u16)) {
+            RegisterRule::Undefined | RegisterRule::SameValue => regs[index],
+            RegisterRule::Offset(offset) => read((cfa as i64 + offset) as u32),
+            RegisterRule::ValOffset(offset) => Some((cfa as i64 + offset) as u32),
+            RegisterRule::Register(register) => *regs.get(register.0 as usize)?,
+            _ => None,
+        };
+    }
+    let return_address = (*caller.get(ra_column.0 as usize)?)?;
+    caller[REG_SP] = Some(cfa);
+    Some((caller, return_address))
+}
+
+/// 从 EPC、RA 和 SP 开始回溯
+///
+/// 指定了带调用帧信息的 ELF 且有栈数据时按调用帧信息展开，否则（或第一层就无法展开时）
+/// 依次取 RA 和栈数据中看起来像返回地址的值，后者可能包含已经返回的函数留下的旧值。
+pub fn backtrace(
+    epc: Option<u32>,
+    regs: &[Option<u32>; 32],
+    stack: Option<&StackDump>,
+    symbolizer: Option<&Symbolizer>,
+) -> Vec<BacktraceFrame> {
+    let mut addresses = Vec::new(); // (地址, 来源, 所在的栈地址)
+    if let Some(epc) = epc {
+        addresses.push((epc, UnwindMethod::Epc, None));
+    }
+
+    // 看起来像代码地址：有 ELF 时位于代码段，否则与 EPC 位于同一个 16MB 区域且不在栈数据内；
+    // 指令至少按 2 字节对齐，很小的值一般是计数之类的整数
+    let is_code = |address: u32| {
+        if address < 0x1000 || address & 1 != 0 {
+            return false;
+        }
+        match (symbolizer, epc) {
+            (Some(symbolizer), _) => symbolizer.is_code(address as u64),
+            (None, Some(epc)) => {
+                address & 0xFF00_0000 == epc & 0xFF00_0000
+                    && !stack.is_some_and(|stack| stack.contains(address))
+            }
+            (None, None) => false,
+        }
+    };
+
+    let cfi = symbolizer.and_then(|symbolizer| symbolizer.cfi());
+    if let (Some(cfi), Some(stack), Some(epc)) = (cfi, stack, epc) {
+        let mut regs = *regs;
+        let mut pc = epc;
+        while addresses.len() < MAX_FRAMES {
+            let return_address = addresses.len() > 1;
+            let Some((caller, ra)) = cfi.step(pc, return_address, &regs, |a| stack.read(a)) else {
+                break;
+            };
+            // 栈指针没有变化时继续展开只会得到同样的结果
+            if !is_code(ra) || (ra == pc && caller[REG_SP] == regs[REG_SP]) {
+                break;
+            }
+            addresses.push((ra, UnwindMethod::Cfi, None));
+            regs = caller;
+            pc = ra;
+        }
+    }
+
+    if addresses.len() <= 1 {
+        if let Some(ra) = regs[REG_RA].filter(|&ra| is_code(ra) && Some(ra) != epc) {
+            addresses.push((ra, UnwindMethod::Ra, None));
+        }
+        if let (Some(stack), Some(sp)) = (stack, regs[REG_SP]) {
+            let mut address = sp.max(stack.start) & !3;
+            while address < stack.end() && addresses.len() < MAX_FRAMES {
+                if let Some(value) = stack.read(address).filter(|&v| is_code(v)) {
+                    if addresses.last().map(|a| a.0) != Some(value) {
+                        addresses.push((value, UnwindMethod::Scan, Some(address)));
+                    }
+                }
+                address += 4;
+            }
+        }
+    }
+
+    addresses
+        .into_iter()
+        .map(|(address, method, stack_address)| BacktraceFrame {
+            address: format!("{:#010X}", address),
+            method,
+            stack_address: stack_address.map(|a| format!("{:#010X}", a)),
+            frames: symbolizer
+                .map(|s| s.symbolize(address as u64, method != UnwindMethod::Epc))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use regex::{Captures, Regex};
+
+use super::decoder::format_bd_addr;
+use super::packet::PacketType;
+
+// 命令、事件参数的起始位置：命令为 opcode(2) len(1)，事件为 code(1) len(1)
+const CMD_PARAMS: usize = 3;
+const EVT_PARAMS: usize = 2;
+// Command_Complete 返回参数的起始位置：num_packets(1) opcode(2) 之后
+const CC_RETURN_PARAMS: usize = EVT_PARAMS + 3;
+// LE Meta 子事件参数的起始位置
+const LE_PARAMS: usize = EVT_PARAMS + 1;
+
+// 数组参数中每一项的长度，各项的参数依次排列
+// Inquiry_Result: bd_addr(6) page_scan_repetition_mode(1) reserved(2) class_of_device(3) clock_offset(2)
+// Inquiry_Result_with_RSSI: bd_addr(6) page_scan_repetition_mode(1) reserved(1) class_of_device(3) clock_offset(2) rssi(1)
+const INQUIRY_RESULT_LEN: usize = 14;
+// Extended_Inquiry_Result: bd_addr(6) ... rssi(1) eir(240)
+const EXTENDED_INQUIRY_RESULT_LEN: usize = 254;
+// Write_Stored_Link_Key / Return_Link_Keys: bd_addr(6) link_key(16)
+const LINK_KEY_ENTRY_LEN: usize = 22;
+
+// 返回参数为 status(1) bd_addr(6) 的命令
+const ADDR_RETURN_COMMANDS: [u16; 17] = [
+    0x0408, 0x040B, 0x040C, 0x040D, 0x040E, 0x041A, 0x042B, 0x042C, 0x042D, 0x042E, 0x042F, 0x0430,
+    0x0433, 0x0434, 0x1009, 0x202B, 0x202C,
+];
+
+/// 蓝牙地址和密钥匿名化
+///
+/// 同一个地址总是替换为同一个匿名地址（按出现顺序编号，保留最高两位以区分 LE 随机地址类型），
+/// 链路密钥、LTK、IRK、PIN 码等直接清零。全 0 和全 FF 的地址不是真实设备，保持不变。
+pub struct Anonymizer {
+    addresses: HashMap<[u8; 6], [u8; 6]>,
+    order: Vec<[u8; 6]>, // 原地址，按出现顺序
+    text_addr: Regex,    // 文本中 "AA:BB:CC:DD:EE:FF" 形式的地址
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self {
+            addresses: HashMap::new(),
+            order: Vec::new(),
+            text_addr: Regex::new(r"\b[0-9A-Fa-f]{2}(?::[0-9A-Fa-f]{2}){5}\b").unwrap(),
+        }
+    }
+}
+
+impl Anonymizer {
+    /// 匿名化一个数据包（不含 H4 头）
+    pub fn anonymize_packet(&mut self, packet_type: PacketType, data: &mut [u8]) {
+        match packet_type {
+            PacketType::Cmd => self.anonymize_command(data),
+            PacketType::Evt => self.anonymize_event(data),
+            PacketType::Acl => self.anonymize_smp(data),
+            PacketType::Sco | PacketType::Iso => {}
+        }
+    }
+
+    /// 替换文本中的地址，用于固件打印的文本行
+    ///
+    /// 只识别 "AA:BB:CC:DD:EE:FF" 形式的地址，其他写法的地址和密钥保持原样。
+    pub fn anonymize_text(&mut self, text: &str) -> String {
+        let text_addr = self.text_addr.clone();
+        text_addr
+            .replace_all(text, |caps: &Captures| {
+                // 文本中的地址高字节在前
+                let mut addr = [0u8; 6];
+                for (i, byte) in caps[0].split(':').rev().enumerate() {
+                    addr[i] = u8::from_str_radix(byte, 16).unwrap_or(0);
+                }
+                format_bd_addr(&self.map_address(addr))
+            })
+            .into_owned()
+    }
+
+    /// 匿名化过的地址数
+    pub fn address_count(&self) -> usize {
+        self.order.len()
+    }
+
+    /// 写入 "原地址 => 匿名地址" 对照表
+    pub fn write_mapping(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "# original => anonymized")?;
+        for addr in &self.order {
+            writeln!(
+                file,
+                "{} => {}",
+                format_bd_addr(addr),
+                format_bd_addr(&self.addresses[addr])
+            )?;
+        }
+        file.flush()
+    }
+
+    fn anonymize_command(&mut self, data: &mut [u8]) {
+        let Some(opcode) = read_u16(data, 0) else {
+            return;
+        };
+        let p = CMD_PARAMS;
+        match opcode {
+            // 参数以 BD_ADDR 开头
+            0x0405 | 0x0408 | 0x0409 | 0x040A | 0x040C | 0x040E | 0x0419 | 0x041A | 0x0429
+            | 0x042A | 0x042B | 0x042C | 0x042D | 0x042F | 0x0433 | 0x0434 | 0x043E | 0x080B
+            | 0x0C0D | 0x0C12 | 0x2005 => self.remap(data, p),
+            // Link_Key_Request_Reply: bd_addr(6) link_key(16)
+            0x040B => {
+                self.remap(data, p);
+                zero(data, p + 6, 16);
+            }
+            // PIN_Code_Request_Reply: bd_addr(6) pin_length(1) pin(16)
+            0x040D => {
+                self.remap(data, p);
+                zero(data, p + 7, 16);
+            }
+            // User_Passkey_Request_Reply: bd_addr(6) passkey(4)
+            0x042E => {
+                self.remap(data, p);
+                zero(data, p + 6, 4);
+            }
+            // Remote_OOB_Data_Request_Reply: bd_addr(6) c(16) r(16)
+            0x0430 => {
+                self.remap(data, p);
+                zero(data, p + 6, 32);
+            }
+            // Write_Stored_Link_Key: num_keys(1) [bd_addr(6) link_key(16)]...
+            0x0C11 => self.remap_keys(data, p),
+            // 地址类型或广播句柄(1) 之后为 BD_ADDR
+            0x2011 | 0x2012 | 0x2028 | 0x202B | 0x202C | 0x2035 | 0x2047 | 0x2048 | 0x204E => {
+                self.remap(data, p + 1)
+            }
+            // LE_Set_Advertising_Parameters: interval(4) type(1) own_type(1) peer_type(1) peer_addr
+            0x2006 => self.remap(data, p + 7),
+            // LE_Create_Connection: scan(4) filter_policy(1) peer_type(1) peer_addr
+            0x200D => self.remap(data, p + 6),
+            // LE_Extended_Create_Connection: filter_policy(1) own_type(1) peer_type(1) peer_addr
+            // LE_Periodic_Advertising_Create_Sync: options(1) sid(1) type(1) addr
+            0x2043 | 0x2044 => self.remap(data, p + 3),
+            // LE_Set_Extended_Advertising_Parameters: handle(1) properties(2) interval(6)
+            // channel_map(1) own_type(1) peer_type(1) peer_addr
+            0x2036 => self.remap(data, p + 12),
+            // LE_Enable_Encryption: handle(2) random(8) ediv(2) ltk(16)
+            0x2019 => zero(data, p + 12, 16),
+            // LE_Long_Term_Key_Request_Reply: handle(2) ltk(16)
+            0x201A => zero(data, p + 2, 16),
+            // LE_Add_Device_To_Resolving_List: type(1) addr(6) peer_irk(16) local_irk(16)
+            0x2027 => {
+                self.remap(data, p + 1);
+                zero(data, p + 7, 32);
+            }
+            _ => {}
+        }
+    }
+
+    fn anonymize_event(&mut self, data: &mut [u8]) {
+        let Some(&code) = data.first() else {
+            return;
+        };
+        let p = EVT_PARAMS;
+        match code {
+            // Connection_Complete / Synchronous_Connection_Complete: status(1) handle(2) bd_addr
+            0x03 | 0x2C => self.remap(data, p + 3),
+            // 参数以 BD_ADDR 开头
+            0x04 | 0x16 | 0x17 | 0x31 | 0x32 | 0x33 | 0x34 | 0x35 | 0x3C | 0x3D => {
+                self.remap(data, p)
+            }
+            // status(1) bd_addr
+            0x07 | 0x12 | 0x36 => self.remap(data, p + 1),
+            // Inquiry_Result / Inquiry_Result_with_RSSI / Extended_Inquiry_Result: num(1) [bd_addr ...]...
+            0x02 | 0x22 | 0x2F => {
+                let num = data.get(p).copied().unwrap_or(0) as usize;
+                let len = match code {
+                    0x2F => EXTENDED_INQUIRY_RESULT_LEN,
+                    _ => INQUIRY_RESULT_LEN,
+                };
+                for i in 0..num {
+                    self.remap(data, p + 1 + i * len);
+                }
+            }
+            // Return_Link_Keys: num_keys(1) [bd_addr(6) link_key(16)]...
+            0x15 => self.remap_keys(data, p),
+            // Link_Key_Notification: bd_addr(6) link_key(16) key_type(1)
+            0x18 => {
+                self.remap(data, p);
+                zero(data, p + 6, 16);
+            }
+            // User_Passkey_Notification: bd_addr(6) passkey(4)
+            0x3B => {
+                self.remap(data, p);
+                zero(data, p + 6, 4);
+            }
+            0x0E => {
+                let opcode = read_u16(data, p + 1);
+                if opcode.is_some_and(|opcode| ADDR_RETURN_COMMANDS.contains(&opcode)) {
+                    self.remap(data, CC_RETURN_PARAMS + 1);
+                }
+            }
+            0x3E => self.anonymize_le_meta(data),
+            _ => {}
+        }
+    }
+
+    fn anonymize_le_meta(&mut self, data: &mut [u8]) {
+        let Some(&subevent) = data.get(EVT_PARAMS) else {
+            return;
+        };
+        let p = LE_PARAMS;
+        match subevent {
+            // LE_Connection_Complete: status(1) handle(2) role(1) peer_type(1) peer_addr
+            0x01 => self.remap(data, p + 5),
+            // LE_Enhanced_Connection_Complete (v1/v2): ... peer_addr local_rpa peer_rpa
+            0x0A | 0x29 => {
+                self.remap(data, p + 5);
+                self.remap(data, p + 11);
+                self.remap(data, p + 17);
+            }
+            // LE_Advertising_Report: num(1) [event_type(1) type(1) addr(6) len(1) data rssi(1)]...
+            0x02 => {
+                let num = data.get(p).copied().unwrap_or(0);
+                let mut offset = p + 1;
+                for _ in 0..num {
+                    self.remap(data, offset + 2);
+                    let Some(&len) = data.get(offset + 8) else {
+                        break;
+                    };
+                    offset += 10 + len as usize;
+                }
+            }
+            // LE_Directed_Advertising_Report: num(1) [event_type(1) type(1) addr(6) direct_type(1) direct_addr(6) rssi(1)]...
+            0x0B => {
+                let num = data.get(p).copied().unwrap_or(0) as usize;
+                for i in 0..num {
+                    let offset = p + 1 + i * 16;
+                    self.remap(data, offset + 2);
+                    self.remap(data, offset + 9);
+                }
+            }
+            // LE_Extended_Advertising_Report: num(1) [event_type(2) type(1) addr(6) ... direct_addr(6) len(1) data]...
+            0x0D => {
+                let num = data.get(p).copied().unwrap_or(0);
+                let mut offset = p + 1;
+                for _ in 0..num {
+                    self.remap(data, offset + 3);
+                    self.remap(data, offset + 17);
+                    let Some(&len) = data.get(offset + 23) else {
+                        break;
+                    };
+                    offset += 24 + len as usize;
+                }
+            }
+            // LE_Periodic_Advertising_Sync_Established: status(1) sync_handle(2) sid(1) type(1) addr
+            0x0E => self.remap(data, p + 5),
+            // LE_Scan_Request_Received: adv_handle(1) type(1) addr
+            0x13 => self.remap(data, p + 2),
+            // LE_Periodic_Advertising_Sync_Transfer_Received: status(1) handle(2) service_data(2) sync_handle(2) sid(1) type(1) addr
+            0x18 => self.remap(data, p + 9),
+            _ => {}
+        }
+    }
+
+    // SMP 分发的密钥和身份地址：只处理起始分片中的 SMP 信道（LE 0x0006，BR/EDR 0x0007）
+    fn anonymize_smp(&mut self, data: &mut [u8]) {
+        let Some(raw_handle) = read_u16(data, 0) else {
+            return;
+        };
+        if (raw_handle >> 12) & 0x03 == 0x01 || !matches!(read_u16(data, 6), Some(0x0006 | 0x0007))
+        {
+            return;
+        }
+        // ACL 头(4) + L2CAP 头(4) 之后为 SMP code(1)
+        let p = 9;
+        match data.get(8) {
+            // Encryption_Information (LTK) / Identity_Information (IRK) / Signing_Information (CSRK)
+            Some(0x06 | 0x08 | 0x0A) => zero(data, p, 16),
+            // Identity_Address_Information: type(1) addr
+            Some(0x09) => self.remap(data, p + 1),
+            _ => {}
+        }
+    }
+
+    // num_keys(1) 之后为 num_keys 组 bd_addr(6) link_key(16)
+    fn remap_keys(&mut self, data: &mut [u8], offset: usize) {
+        let num = data.get(offset).copied().unwrap_or(0) as usize;
+        for i in 0..num {
+            let entry = offset + 1 + i * LINK_KEY_ENTRY_LEN;
+            self.remap(data, entry);
+            zero(data, entry + 6, 16);
+        }
+    }
+
+    // 替换 offset 处的 BD_ADDR，数据不足 6 字节时忽略
+    fn remap(&mut self, data: &mut [u8], offset: usize) {
+        let Some(slot) = data.get_mut(offset..offset + 6) else {
+            return;
+        };
+        let mut addr = [0u8; 6];
+        addr.copy_from_slice(slot);
+        slot.copy_from_slice(&self.map_address(addr));
+    }
+
+    fn map_address(&mut self, addr: [u8; 6]) -> [u8; 6] {
+        if addr == [0x00; 6] || addr == [0xFF; 6] {
+            return addr;
+        }
+        if let Some(mapped) = self.addresses.get(&addr) {
+            return *mapped;
+        }
+
+        // 按出现顺序编号，最高字节保留原地址的最高两位（LE 随机地址的类型）
+        let index = self.order.len() as u32 + 1;
+        let mut mapped = [0u8; 6];
+        mapped[..4].copy_from_slice(&index.to_le_bytes());
+        mapped[5] = addr[5] & 0xC0;
+        self.addresses.insert(addr, mapped);
+        self.order.push(addr);
+        mapped
+    }
+}
+
+// 清零 offset 开始的 len 字节，数据不足时清零剩余部分
+fn zero(data: &mut [u8], offset: usize, len: usize) {
+    let end = (offset + len).min(data.len());
+    if let Some(bytes) = data.get_mut(offset..end) {
+        bytes.fill(0);
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用的地址，最高字节 0xC5 为 LE 静态随机地址
+    fn addr(n: u8) -> [u8; 6] {
+        [n, 0x11, 0x22, 0x33, 0x44, 0xC5]
+    }
+
+    fn slot(data: &[u8], offset: usize) -> [u8; 6] {
+        data[offset..offset + 6].try_into().unwrap()
+    }
+
+    // 命令：opcode(2) len(1) 参数
+    fn command(opcode: u16, params: &[u8]) -> Vec<u8> {
+        let mut data = opcode.to_le_bytes().to_vec();
+        data.push(params.len() as u8);
+        data.extend_from_slice(params);
+        data
+    }
+
+    // 事件：code(1) len(1) 参数
+    fn event(code: u8, params: &[u8]) -> Vec<u8> {
+        let mut data = vec![code, params.len() as u8];
+        data.extend_from_slice(params);
+        data
+    }
+
+    // 地址被替换为匿名地址，且与之前同一地址的结果一致
+    fn assert_remapped(anonymizer: &Anonymizer, data: &[u8], offset: usize, original: [u8; 6]) {
+        let mapped = slot(data, offset);
+        assert_ne!(mapped, original);
+        assert_eq!(anonymizer.addresses[&original], mapped);
+        assert_eq!(mapped[5], original[5] & 0xC0);
+    }
+
+    #[test]
+    fn link_key_notification() {
+        let mut anonymizer = Anonymizer::default();
+        let mut params = addr(1).to_vec();
+        params.extend([0xAA; 16]);
+        params.push(0x04); // key_type
+        let mut data = event(0x18, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_remapped(&anonymizer, &data, 2, addr(1));
+        assert_eq!(data[8..24], [0; 16]);
+        assert_eq!(data[24], 0x04);
+    }
+
+    #[test]
+    fn pin_code_request_reply() {
+        let mut anonymizer = Anonymizer::default();
+        let mut params = addr(2).to_vec();
+        params.push(4); // pin_length
+        params.extend(b"1234");
+        params.extend([0; 12]);
+        let mut data = command(0x040D, &params);
+        anonymizer.anonymize_packet(PacketType::Cmd, &mut data);
+
+        assert_remapped(&anonymizer, &data, 3, addr(2));
+        assert_eq!(data[9], 4);
+        assert_eq!(data[10..26], [0; 16]);
+    }
+
+    #[test]
+    fn switch_role() {
+        let mut anonymizer = Anonymizer::default();
+        let mut params = addr(3).to_vec();
+        params.push(0x01); // role
+        let mut data = command(0x080B, &params);
+        anonymizer.anonymize_packet(PacketType::Cmd, &mut data);
+
+        assert_remapped(&anonymizer, &data, 3, addr(3));
+        assert_eq!(data[9], 0x01);
+    }
+
+    #[test]
+    fn le_long_term_key_request_reply() {
+        let mut anonymizer = Anonymizer::default();
+        let mut params = vec![0x40, 0x00]; // handle
+        params.extend([0xBB; 16]);
+        let mut data = command(0x201A, &params);
+        anonymizer.anonymize_packet(PacketType::Cmd, &mut data);
+
+        assert_eq!(data[3..5], [0x40, 0x00]);
+        assert_eq!(data[5..21], [0; 16]);
+    }
+
+    #[test]
+    fn le_advertising_report() {
+        let mut anonymizer = Anonymizer::default();
+        // subevent(1) num(1) [event_type(1) type(1) addr(6) len(1) data rssi(1)]...
+        let mut params = vec![0x02, 2];
+        params.extend([0x00, 0x01]);
+        params.extend(addr(3));
+        params.extend([3, 0x02, 0x01, 0x06, 0xC0]);
+        params.extend([0x04, 0x01]);
+        params.extend(addr(4));
+        params.extend([0, 0xB0]);
+        let mut data = event(0x3E, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_remapped(&anonymizer, &data, 6, addr(3));
+        assert_eq!(data[12..17], [3, 0x02, 0x01, 0x06, 0xC0]);
+        assert_remapped(&anonymizer, &data, 19, addr(4));
+        assert_eq!(data[25..27], [0, 0xB0]);
+        assert_ne!(slot(&data, 6), slot(&data, 19));
+    }
+
+    #[test]
+    fn le_enhanced_connection_complete() {
+        let mut anonymizer = Anonymizer::default();
+        // 先出现在 Link_Key_Notification 中的地址
+        let mut params = addr(5).to_vec();
+        params.extend([0xAA; 16]);
+        params.push(0x04);
+        let mut link_key = event(0x18, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut link_key);
+
+        // subevent(1) status(1) handle(2) role(1) peer_type(1) peer_addr(6) local_rpa(6) peer_rpa(6)
+        // interval(2) latency(2) timeout(2) clock_accuracy(1)
+        let mut params = vec![0x0A, 0x00, 0x40, 0x00, 0x00, 0x01];
+        params.extend(addr(5));
+        params.extend(addr(6));
+        params.extend(addr(7));
+        params.extend([0x18, 0x00, 0x00, 0x00, 0xC8, 0x00, 0x00]);
+        let mut data = event(0x3E, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_eq!(data[3..8], [0x00, 0x40, 0x00, 0x00, 0x01]);
+        assert_remapped(&anonymizer, &data, 8, addr(5));
+        assert_eq!(slot(&data, 8), slot(&link_key, 2));
+        assert_remapped(&anonymizer, &data, 14, addr(6));
+        assert_remapped(&anonymizer, &data, 20, addr(7));
+        assert_eq!(data[26..], [0x18, 0x00, 0x00, 0x00, 0xC8, 0x00, 0x00]);
+        assert_eq!(anonymizer.address_count(), 3);
+    }
+
+    #[test]
+    fn read_bd_addr_complete() {
+        let mut anonymizer = Anonymizer::default();
+        // num_packets(1) opcode(2) status(1) bd_addr(6)
+        let mut params = vec![0x01, 0x09, 0x10, 0x00];
+        params.extend(addr(8));
+        let mut data = event(0x0E, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_eq!(data[2..6], [0x01, 0x09, 0x10, 0x00]);
+        assert_remapped(&anonymizer, &data, 6, addr(8));
+
+        // 同一地址出现在文本中时替换为同一个匿名地址
+        let text = anonymizer.anonymize_text("local C5:44:33:22:11:08");
+        assert_eq!(
+            text,
+            format!("local {}", format_bd_addr(&anonymizer.addresses[&addr(8)]))
+        );
+    }
+
+    #[test]
+    fn inquiry_result_entries() {
+        let mut anonymizer = Anonymizer::default();
+        // num(1) [bd_addr(6) page_scan_repetition_mode(1) reserved(2) class_of_device(3) clock_offset(2)]...
+        let mut params = vec![2];
+        for n in [9, 10] {
+            params.extend(addr(n));
+            params.extend([0x01, 0x00, 0x00, 0x0C, 0x02, 0x5A, 0x34, 0x12]);
+        }
+        let mut data = event(0x02, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_remapped(&anonymizer, &data, 3, addr(9));
+        assert_remapped(&anonymizer, &data, 17, addr(10));
+        assert_eq!(
+            data[9..17],
+            [0x01, 0x00, 0x00, 0x0C, 0x02, 0x5A, 0x34, 0x12]
+        );
+        assert_eq!(
+            data[23..31],
+            [0x01, 0x00, 0x00, 0x0C, 0x02, 0x5A, 0x34, 0x12]
+        );
+    }
+
+    #[test]
+    fn return_link_keys_entries() {
+        let mut anonymizer = Anonymizer::default();
+        // num_keys(1) [bd_addr(6) link_key(16)]...
+        let mut params = vec![2];
+        for n in [11, 12] {
+            params.extend(addr(n));
+            params.extend([0xCC; 16]);
+        }
+        let mut data = event(0x15, &params);
+        anonymizer.anonymize_packet(PacketType::Evt, &mut data);
+
+        assert_remapped(&anonymizer, &data, 3, addr(11));
+        assert_eq!(data[9..25], [0; 16]);
+        assert_remapped(&anonymizer, &data, 25, addr(12));
+        assert_eq!(data[31..47], [0; 16]);
+    }
+}
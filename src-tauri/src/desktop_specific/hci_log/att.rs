@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+// 摘要中最多显示的属性值字节数
+const MAX_VALUE_BYTES: usize = 32;
+
+// 一次 GATT 操作
+#[derive(Serialize, Debug, Clone)]
+pub struct GattTransaction {
+    pub frame: usize,                  // 请求所在的帧序号
+    pub line: usize,                   // 请求所在的行号
+    pub time: String,                  // 日志中请求的时间戳
+    pub connection: u16,               // 连接句柄
+    pub direction: String,             // 请求方向 (=>, <=)
+    pub operation: String,             // 操作，例如 Read_Request、Handle_Value_Notification
+    pub att_handle: Option<u16>,       // 属性句柄
+    pub value: Option<String>,         // 写入、通知、指示或读取到的值（十六进制）
+    pub result: Option<String>,        // 响应，失败时为错误原因
+    pub response_frame: Option<usize>, // 响应所在的帧序号
+    pub latency_ms: Option<f64>,       // 请求到响应的时间
+}
+
+/// GATT 操作时间线
+///
+/// ATT 每个方向同时只有一个未完成的请求（指示也只有一个未确认），
+/// 因此按 (连接句柄, 请求方向) 把响应与请求配对。
+/// 通知和写命令没有响应，只记录操作本身。
+#[derive(Default)]
+pub struct GattTimeline {
+    transactions: Vec<GattTransaction>,
+    pending: HashMap<(u16, bool), (usize, u64)>, // (连接句柄, 请求方向) -> (序号, 请求时间戳)
+}
+
+// ATT PDU 所在的记录
+pub struct AttRecord<'a> {
+    pub frame: usize,
+    pub line: usize,
+    pub time: &'a str,
+    pub timestamp: u64, // 微秒
+    pub connection: u16,
+    pub rx: bool,
+}
+
+impl GattTimeline {
+    pub fn push(&mut self, record: AttRecord, pdu: &[u8]) {
+        let Some(&opcode) = pdu.first() else {
+            return;
+        };
+        let params = &pdu[1..];
+
+        match kind(opcode) {
+            Kind::Request | Kind::Command => {
+                let value = match opcode {
+                    // 写请求/写命令/指示：handle(2) value
+                    0x12 | 0x1D | 0x52 | 0xD2 => params.get(2..),
+                    // 准备写：handle(2) offset(2) value
+                    0x16 => params.get(4..),
+                    _ => None,
+                };
+                let index = self.transactions.len();
+                self.transactions.push(GattTransaction {
+                    frame: record.frame,
+                    line: record.line,
+                    time: record.time.to_string(),
+                    connection: record.connection,
+                    direction: direction(record.rx),
+                    operation: opcode_name(opcode),
+                    att_handle: request_handle(opcode, params),
+                    value: value.map(format_value),
+                    result: None,
+                    response_frame: None,
+                    latency_ms: None,
+                });
+                if kind(opcode) == Kind::Request {
+                    self.pending
+                        .insert((record.connection, record.rx), (index, record.timestamp));
+                }
+            }
+            Kind::Response => {
+                let Some((index, request_ts)) =
+                    self.pending.remove(&(record.connection, !record.rx))
+                else {
+                    return;
+                };
+                let transaction = &mut self.transactions[index];
+                transaction.result = Some(match opcode {
+                    // Error_Response: request_opcode(1) handle(2) error_code(1)
+                    0x01 => match params.get(3) {
+                        Some(&code) => format!("Error_Response: {}", error_name(code)),
+                        None => "Error_Response".to_string(),
+                    },
+                    _ => opcode_name(opcode),
+                });
+                if matches!(opcode, 0x0B | 0x0D | 0x0F | 0x21) {
+                    transaction.value = Some(format_value(params));
+                }
+                transaction.response_frame = Some(record.frame);
+                transaction.latency_ms =
+                    Some(record.timestamp.saturating_sub(request_ts) as f64 / 1000.0);
+            }
+            Kind::Notification => {
+                self.transactions.push(GattTransaction {
+                    frame: record.frame,
+                    line: record.line,
+                    time: record.time.to_string(),
+                    connection: record.connection,
+                    direction: direction(record.rx),
+                    operation: opcode_name(opcode),
+                    att_handle: request_handle(opcode, params),
+                    value: params.get(2..).map(format_value),
+                    result: None,
+                    response_frame: None,
+                    latency_ms: None,
+                });
+            }
+            Kind::Unknown => {}
+        }
+    }
+
+    pub fn into_transactions(self) -> Vec<GattTransaction> {
+        self.transactions
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Request,      // 需要响应，包括指示
+    Response,     // 响应，包括确认
+    Command,      // 写命令，没有响应
+    Notification, // 通知
+    Unknown,
+}
+
+fn kind(opcode: u8) -> Kind {
+    match opcode {
+        0x02 | 0x04 | 0x06 | 0x08 | 0x0A | 0x0C | 0x0E | 0x10 | 0x12 | 0x16 | 0x18 | 0x1D
+        | 0x20 => Kind::Request,
+        0x01 | 0x03 | 0x05 | 0x07 | 0x09 | 0x0B | 0x0D | 0x0F | 0x11 | 0x13 | 0x17 | 0x19
+        | 0x1E | 0x21 => Kind::Response,
+        0x52 | 0xD2 => Kind::Command,
+        0x1B | 0x23 => Kind::Notification,
+        _ => Kind::Unknown,
+    }
+}
+
+fn direction(rx: bool) -> String {
+    if rx { "<=" } else { "=>" }.to_string()
+}
+
+// 参数以属性句柄开头的 PDU
+fn request_handle(opcode: u8, params: &[u8]) -> Option<u16> {
+    match opcode {
+        0x0A | 0x0C | 0x12 | 0x16 | 0x1B | 0x1D | 0x52 | 0xD2 => {
+            Some(u16::from_le_bytes([*params.first()?, *params.get(1)?]))
+        }
+        _ => None,
+    }
+}
+
+/// 生成一个 ATT PDU 的可读摘要
+pub fn summarize(pdu: &[u8]) -> String {
+    let Some(&opcode) = pdu.first() else {
+        return "ATT (truncated)".to_string();
+    };
+    let params = &pdu[1..];
+    let read = |offset: usize| {
+        Some(u16::from_le_bytes([
+            *params.get(offset)?,
+            *params.get(offset + 1)?,
+        ]))
+    };
+
+    let mut summary = format!("ATT {}", opcode_name(opcode));
+    match opcode {
+        0x01 => {
+            if let (Some(&request), Some(handle), Some(&code)) =
+                (params.first(), read(1), params.get(3))
+            {
+                summary.push_str(&format!(
+                    " request={} handle=0x{:04x} error={}",
+                    opcode_name(request),
+                    handle,
+                    error_name(code)
+                ));
+            }
+        }
+        // Exchange_MTU_Request/Response
+        0x02 | 0x03 => {
+            if let Some(mtu) = read(0) {
+                summary.push_str(&format!(" mtu={}", mtu));
+            }
+        }
+        // 参数为起止句柄的请求
+        0x04 | 0x06 | 0x08 | 0x10 => {
+            if let (Some(start), Some(end)) = (read(0), read(2)) {
+                summary.push_str(&format!(" range=0x{:04x}-0x{:04x}", start, end));
+            }
+        }
+        // 带值的响应
+        0x0B | 0x0D => summary.push_str(&format!(" value={}", format_value(params))),
+        _ => {
+            if let Some(handle) = request_handle(opcode, params) {
+                summary.push_str(&format!(" handle=0x{:04x}", handle));
+            }
+            let value = match opcode {
+                0x12 | 0x1B | 0x1D | 0x52 | 0xD2 => params.get(2..),
+                _ => None,
+            };
+            if let Some(value) = value {
+                summary.push_str(&format!(" value={}", format_value(value)));
+            }
+        }
+    }
+    summary
+}
+
+fn format_value(value: &[u8]) -> String {
+    let mut text = value
+        .iter()
+        .take(MAX_VALUE_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if value.len() > MAX_VALUE_BYTES {
+        text.push_str(" ...");
+    }
+    text
+}
+
+/// ATT 操作名称
+pub fn opcode_name(opcode: u8) -> String {
+    let name = match opcode {
+        0x01 => "Error_Response",
+        0x02 => "Exchange_MTU_Request",
+        0x03 => "Exchange_MTU_Response",
+        0x04 => "Find_Information_Request",
+        0x05 => "Find_Information_Response",
+        0x06 => "Find_By_Type_Value_Request",
+        0x07 => "Find_By_Type_Value_Response",
+        0x08 => "Read_By_Type_Request",
+        0x09 => "Read_By_Type_Response",
+        0x0A => "Read_Request",
+        0x0B => "Read_Response",
+        0x0C => "Read_Blob_Request",
+        0x0D => "Read_Blob_Response",
+        0x0E => "Read_Multiple_Request",
+        0x0F => "Read_Multiple_Response",
+        0x10 => "Read_By_Group_Type_Request",
+        0x11 => "Read_By_Group_Type_Response",
+        0x12 => "Write_Request",
+        0x13 => "Write_Response",
+        0x16 => "Prepare_Write_Request",
+        0x17 => "Prepare_Write_Response",
+        0x18 => "Execute_Write_Request",
+        0x19 => "Execute_Write_Response",
+        0x1B => "Handle_Value_Notification",
+        0x1D => "Handle_Value_Indication",
+        0x1E => "Handle_Value_Confirmation",
+        0x20 => "Read_Multiple_Variable_Request",
+        0x21 => "Read_Multiple_Variable_Response",
+        0x23 => "Multiple_Handle_Value_Notification",
+        0x52 => "Write_Command",
+        0xD2 => "Signed_Write_Command",
+        _ => return format!("ATT(0x{:02x})", opcode),
+    };
+    name.to_string()
+}
+
+/// ATT 错误码名称
+pub fn error_name(code: u8) -> String {
+    let name = match code {
+        0x01 => "Invalid_Handle",
+        0x02 => "Read_Not_Permitted",
+        0x03 => "Write_Not_Permitted",
+        0x04 => "Invalid_PDU",
+        0x05 => "Insufficient_Authentication",
+        0x06 => "Request_Not_Supported",
+        0x07 => "Invalid_Offset",
+        0x08 => "Insufficient_Authorization",
+        0x09 => "Prepare_Queue_Full",
+        0x0A => "Attribute_Not_Found",
+        0x0B => "Attribute_Not_Long",
+        0x0C => "Encryption_Key_Size_Too_Short",
+        0x0D => "Invalid_Attribute_Value_Length",
+        0x0E => "Unlikely_Error",
+        0x0F => "Insufficient_Encryption",
+        0x10 => "Unsupported_Group_Type",
+        0x11 => "Insufficient_Resources",
+        0x12 => "Database_Out_Of_Sync",
+        0x13 => "Value_Not_Allowed",
+        _ => return format!("Error(0x{:02x})", code),
+    };
+    name.to_string()
+}
@@ -0,0 +1,215 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::parser::MAP_FILE_SUFFIX;
+use super::reader::{CaptureReader, CaptureRecord};
+use super::report::HciLogReport;
+use super::writer::{CaptureWriter, PcapngWriter};
+use super::{check_output, output_path, parse_hci_log_do, HciLogOptions};
+
+// 目录中视为日志的文件扩展名
+const LOG_EXTENSIONS: [&str; 2] = ["txt", "log"];
+
+// 合并输出的文件名，位于第一个日志所在目录
+const MERGED_FILE_NAME: &str = "merged.pcapng";
+
+// 单个日志的转换结果
+#[derive(Serialize, Debug)]
+pub struct HciLogFileResult {
+    pub file_path: String,
+    pub report: Option<HciLogReport>, // 转换成功时的结果
+    pub error: Option<String>,        // 转换失败的原因
+}
+
+/// 批量转换结果
+///
+/// 单个文件转换失败不影响其他文件，失败原因记录在对应的 `error` 中。
+#[derive(Serialize, Debug, Default)]
+pub struct HciLogBatchReport {
+    pub files: Vec<HciLogFileResult>, // 每个日志的结果
+    pub merged_file: Option<String>,  // 合并后的 pcapng 文件
+    pub merged_packets: usize,        // 合并文件中的记录数
+}
+
+/// 逐个转换日志，`merge` 时再按时间顺序合并为一个 pcapng，每个日志对应一个接口
+///
+/// `progress` 的参数为 (当前日志路径, 已读取字节数, 文件总字节数)。
+pub fn parse_hci_log_batch_do<P: FnMut(&str, u64, u64)>(
+    paths: &[String],
+    options: &HciLogOptions,
+    merge: bool,
+    mut progress: P,
+) -> io::Result<HciLogBatchReport> {
+    if options.output_path.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Output path can only be set when converting a single log",
+        ));
+    }
+
+    let files = expand_inputs(paths, options)?;
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No log files found",
+        ));
+    }
+
+    // 转换前检查合并输出，避免转换完成后才发现无法写入
+    if merge {
+        check_output(&files[0], &merged_path(&files[0]), options.overwrite)?;
+    }
+
+    let mut batch = HciLogBatchReport::default();
+    for file_path in files {
+        let result = parse_hci_log_do(&file_path, options, |bytes_read, total_bytes| {
+            progress(&file_path, bytes_read, total_bytes)
+        });
+        batch.files.push(match result {
+            Ok(report) => HciLogFileResult {
+                file_path,
+                report: Some(report),
+                error: None,
+            },
+            Err(e) => HciLogFileResult {
+                file_path,
+                report: None,
+                error: Some(e.to_string()),
+            },
+        });
+    }
+
+    if merge {
+        // (接口名称, 该日志输出的抓包文件)
+        let sources: Vec<(String, Vec<String>)> = batch
+            .files
+            .iter()
+            .filter_map(|f| {
+                let report = f.report.as_ref()?;
+                Some((file_name(&f.file_path), report.output_files.clone()))
+            })
+            .collect();
+        if !sources.is_empty() {
+            let merged_file = merged_path(&batch.files[0].file_path);
+            batch.merged_packets = merge_captures(&sources, &merged_file)?;
+            batch.merged_file = Some(merged_file);
+        }
+    }
+
+    Ok(batch)
+}
+
+// 展开输入路径：目录取其中的 .txt/.log 文件（不递归），按文件名排序
+// 目录中本工具之前生成的文件不作为输入，明确选择的文件总是作为输入
+fn expand_inputs(paths: &[String], options: &HciLogOptions) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !Path::new(path).is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            let is_log = entry_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| LOG_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+            if entry_path.is_file() && is_log {
+                entries.push(entry_path.to_string_lossy().into_owned());
+            }
+        }
+        entries.sort();
+        let outputs: Vec<String> = entries
+            .iter()
+            .map(|entry| output_path(entry, options, 1))
+            .collect();
+        entries.retain(|entry| !outputs.contains(entry) && !is_generated_text(entry));
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+// 本工具生成的文本文件：匿名化对照表 `<输出文件>.map.txt`，
+// 以及“抓包转文本”生成的 `<抓包文件>.txt`（与抓包文件位于同一目录）
+fn is_generated_text(path: &str) -> bool {
+    if path.ends_with(MAP_FILE_SUFFIX) {
+        return true;
+    }
+    let Some(capture) = path.strip_suffix(".txt") else {
+        return false;
+    };
+    Path::new(capture).is_file() && CaptureReader::open(capture).is_ok()
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+fn merged_path(first_file: &str) -> String {
+    let dir = Path::new(first_file).parent().unwrap_or(Path::new(""));
+    dir.join(MERGED_FILE_NAME).to_string_lossy().into_owned()
+}
+
+// 一个待合并的抓包文件
+struct MergeStream {
+    interface_id: u32,
+    reader: CaptureReader<BufReader<File>>,
+    head: Option<CaptureRecord>, // 下一条记录
+}
+
+// 按时间顺序合并，时间相同时按来源顺序，返回写入的记录数
+//
+// 每个文件内部的时间已经单调递增，每次取各文件下一条记录中最早的一条即可，
+// 不需要把所有记录读入内存。
+fn merge_captures(sources: &[(String, Vec<String>)], merged_file: &str) -> io::Result<usize> {
+    let names: Vec<String> = sources.iter().map(|(name, _)| name.clone()).collect();
+    let mut writer = PcapngWriter::create_with_interfaces(merged_file, &names)?;
+
+    let mut streams = Vec::new();
+    for (interface_id, (_, files)) in sources.iter().enumerate() {
+        for file in files {
+            let mut reader = CaptureReader::open(file)?;
+            let head = reader.next_record()?;
+            streams.push(MergeStream {
+                interface_id: interface_id as u32,
+                reader,
+                head,
+            });
+        }
+    }
+
+    let mut count = 0;
+    loop {
+        let next = streams
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| s.head.as_ref().map(|r| (r.timestamp, i)))
+            .min();
+        let Some((_, i)) = next else {
+            break;
+        };
+
+        let stream = &mut streams[i];
+        let record = stream.head.take().unwrap();
+        writer.write_packet_on(
+            stream.interface_id,
+            record.timestamp,
+            record.flags,
+            &record.data,
+            record.comment.as_deref(),
+        )?;
+        stream.head = stream.reader.next_record()?;
+        count += 1;
+    }
+    writer.flush()?;
+
+    Ok(count)
+}
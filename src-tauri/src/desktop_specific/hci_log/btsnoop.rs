@@ -0,0 +1,462 @@
+// BTSnoop 抓包格式，所有字段均为大端
+//
+// 文件头为 "btsnoop\0"、版本号 1 和数据链路类型，之后是连续的记录，
+// 每条记录为 24 字节记录头加数据。支持两种数据链路类型：
+// - 1001：HCI 数据不带 H4 头，包类型只能从标志位推断；
+// - 1002：HCI UART，数据带 H4 头。
+//
+// 标志位 bit0 为方向（0: 主机发给控制器，1: 控制器发给主机），
+// bit1 区分数据和命令/事件（0: ACL/SCO/ISO，1: 命令/事件）。
+
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+
+use super::writer::CaptureWriter;
+
+// 公元 0 年到 1970 年之间的微秒数，BTSnoop 时间戳以公元 0 年为起点
+pub const BTSNOOP_EPOCH_DELTA_US: u64 = 0x00DCDDB30F2F8000;
+
+pub const FLAG_RECEIVED: u32 = 0x01; // 控制器发给主机
+pub const FLAG_COMMAND_EVENT: u32 = 0x02; // 命令或事件
+
+// 一条记录的最大长度：H4 包类型(1) + ACL/ISO 头(4) + 数据(最长 65535)
+// 用于拒绝损坏的文件中过大的长度，避免按长度分配内存
+pub const MAX_RECORD_LEN: usize = 1 + 4 + 0xFFFF;
+
+const MAGIC: &[u8; 8] = b"btsnoop\0";
+const VERSION: u32 = 1;
+
+// H4 包类型
+const H4_CMD: u8 = 0x01;
+const H4_ACL: u8 = 0x02;
+const H4_EVT: u8 = 0x04;
+
+/// 数据链路类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Datalink {
+    Hci,     // 1001，不带 H4 头
+    HciUart, // 1002，带 H4 头
+}
+
+impl Datalink {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            1001 => Some(Self::Hci),
+            1002 => Some(Self::HciUart),
+            _ => None,
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::Hci => 1001,
+            Self::HciUart => 1002,
+        }
+    }
+}
+
+/// 文件头
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BtsnoopHeader {
+    pub version: u32,
+    pub datalink: Datalink,
+}
+
+impl BtsnoopHeader {
+    pub fn new(datalink: Datalink) -> Self {
+        Self {
+            version: VERSION,
+            datalink,
+        }
+    }
+
+    /// 解析 16 字节文件头
+    pub fn parse(bytes: &[u8; 16]) -> io::Result<Self> {
+        if &bytes[..8] != MAGIC {
+            return Err(invalid_data("Not a BTSnoop file".to_string()));
+        }
+        let version = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        let datalink = u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+        let datalink = Datalink::from_u32(datalink).ok_or_else(|| {
+            invalid_data(format!("Unsupported BTSnoop datalink type {}", datalink))
+        })?;
+        Ok(Self { version, datalink })
+    }
+
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(MAGIC);
+        bytes[8..12].copy_from_slice(&self.version.to_be_bytes());
+        bytes[12..].copy_from_slice(&self.datalink.to_u32().to_be_bytes());
+        bytes
+    }
+}
+
+/// 一条记录
+///
+/// `data` 为文件中保存的内容，数据链路类型为 1001 时不带 H4 头。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtsnoopRecord {
+    pub original_len: u32, // 原始长度，抓包时被截断时大于 data 的长度
+    pub flags: u32,        // 标志
+    pub drops: u32,        // 丢包计数
+    pub timestamp: u64,    // 公元 0 年起的微秒数
+    pub data: Vec<u8>,
+}
+
+impl BtsnoopRecord {
+    /// 由带 H4 头的数据包生成记录，1001 去掉 H4 头
+    pub fn from_h4(datalink: Datalink, timestamp: u64, flags: u32, data: &[u8]) -> Self {
+        let data = match datalink {
+            Datalink::Hci => data.get(1..).unwrap_or_default(),
+            Datalink::HciUart => data,
+        };
+        Self {
+            original_len: data.len() as u32,
+            flags,
+            drops: 0,
+            timestamp,
+            data: data.to_vec(),
+        }
+    }
+
+    /// 带 H4 头的数据包，1001 根据标志位补上包类型
+    ///
+    /// 1001 的标志位无法区分 ACL/SCO/ISO，数据一律按 ACL 处理。
+    pub fn to_h4(&self, datalink: Datalink) -> Vec<u8> {
+        match datalink {
+            Datalink::Hci => {
+                let mut data = Vec::with_capacity(self.data.len() + 1);
+                data.push(h4_type_from_flags(self.flags));
+                data.extend_from_slice(&self.data);
+                data
+            }
+            Datalink::HciUart => self.data.clone(),
+        }
+    }
+
+    /// 读取一条记录，文件结束时返回 `None`
+    ///
+    /// 记录头不完整时同样视为文件结束，实时抓取的文件末尾可能只写入了一部分。
+    pub fn read<R: Read>(inner: &mut R) -> io::Result<Option<Self>> {
+        let mut header = [0u8; 24];
+        match inner.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let read_u32 = |offset: usize| {
+            u32::from_be_bytes([
+                header[offset],
+                header[offset + 1],
+                header[offset + 2],
+                header[offset + 3],
+            ])
+        };
+        let original_len = read_u32(0);
+        let included_len = read_u32(4) as usize;
+        let flags = read_u32(8);
+        let drops = read_u32(12);
+        let timestamp = u64::from_be_bytes(header[16..24].try_into().unwrap());
+        if included_len > MAX_RECORD_LEN {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("BTSnoop record is too long: {} bytes", included_len),
+            ));
+        }
+
+        let mut data = vec![0u8; included_len];
+        inner.read_exact(&mut data)?;
+
+        Ok(Some(Self {
+            original_len,
+            flags,
+            drops,
+            timestamp,
+            data,
+        }))
+    }
+
+    pub fn write<W: Write>(&self, inner: &mut W) -> io::Result<()> {
+        inner.write_all(&self.original_len.to_be_bytes())?; // 原始长度
+        inner.write_all(&(self.data.len() as u32).to_be_bytes())?; // 包含长度
+        inner.write_all(&self.flags.to_be_bytes())?; // 标志
+        inner.write_all(&self.drops.to_be_bytes())?; // 丢包计数
+        inner.write_all(&self.timestamp.to_be_bytes())?; // 时间戳
+        inner.write_all(&self.data)
+    }
+}
+
+/// 根据 H4 包类型和方向生成标志位
+pub fn flags_from_h4(h4_type: u8, received: bool) -> u32 {
+    let command_or_event = matches!(h4_type, H4_CMD | H4_EVT);
+    (command_or_event as u32) << 1 | received as u32
+}
+
+/// 根据标志位推断 H4 包类型，数据按 ACL 处理
+pub fn h4_type_from_flags(flags: u32) -> u8 {
+    match flags & (FLAG_COMMAND_EVENT | FLAG_RECEIVED) {
+        0x02 => H4_CMD,
+        0x03 => H4_EVT,
+        _ => H4_ACL,
+    }
+}
+
+/// BTSnoop 写入器，默认数据链路类型为 1002（HCI UART）
+pub struct BtsnoopWriter<W: Write> {
+    inner: W,
+    datalink: Datalink,
+}
+
+impl BtsnoopWriter<BufWriter<File>> {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> BtsnoopWriter<W> {
+    pub fn new(inner: W) -> io::Result<Self> {
+        Self::with_datalink(inner, Datalink::HciUart)
+    }
+
+    pub fn with_datalink(mut inner: W, datalink: Datalink) -> io::Result<Self> {
+        // 写入 BTSnoop 文件头
+        inner.write_all(&BtsnoopHeader::new(datalink).to_bytes())?;
+        Ok(Self { inner, datalink })
+    }
+
+    /// 原样写入一条记录，`data` 需与数据链路类型一致
+    pub fn write_record(&mut self, record: &BtsnoopRecord) -> io::Result<()> {
+        record.write(&mut self.inner)
+    }
+}
+
+impl<W: Write> CaptureWriter for BtsnoopWriter<W> {
+    fn write_packet(
+        &mut self,
+        timestamp: u64,
+        flags: u32,
+        data: &[u8],
+        _comment: Option<&str>,
+    ) -> io::Result<()> {
+        // BTSnoop 不支持注释，直接忽略
+        let record = BtsnoopRecord::from_h4(self.datalink, timestamp, flags, data);
+        self.write_record(&record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::super::{parse_hci_log_do, HciLogOptions};
+    use super::*;
+
+    fn test_file(name: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../test")
+            .join(name);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn read_all(mut bytes: &[u8]) -> (BtsnoopHeader, Vec<BtsnoopRecord>) {
+        let mut header = [0u8; 16];
+        bytes.read_exact(&mut header).unwrap();
+        let header = BtsnoopHeader::parse(&header).unwrap();
+        let mut records = Vec::new();
+        while let Some(record) = BtsnoopRecord::read(&mut bytes).unwrap() {
+            records.push(record);
+        }
+        (header, records)
+    }
+
+    fn write_all(datalink: Datalink, records: &[BtsnoopRecord]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut writer = BtsnoopWriter::with_datalink(&mut bytes, datalink).unwrap();
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        bytes
+    }
+
+    // 把 test/ 中的日志转换为 BTSnoop，返回文件内容和写入的数据包数
+    // 输出文件名带上测试名，并行执行的测试不会读写同一个文件
+    fn convert_log(test: &str, name: &str, bluetrum_ts: bool, skip_chars: u32) -> (Vec<u8>, usize) {
+        let output = std::env::temp_dir().join(format!("btsnoop_{}_{}.cfa", test, name));
+        let output = output.to_string_lossy().into_owned();
+        let options: HciLogOptions = serde_json::from_value(serde_json::json!({
+            "bluetrum_ts": bluetrum_ts,
+            "skip_chars": skip_chars,
+            "output_path": output,
+            "overwrite": true,
+        }))
+        .unwrap();
+        let report = parse_hci_log_do(&test_file(name), &options, |_, _| {}).unwrap();
+        let bytes = fs::read(&output).unwrap();
+        fs::remove_file(&output).unwrap();
+        (bytes, report.packets)
+    }
+
+    const TEST_LOGS: [(&str, bool, u32); 7] = [
+        ("test_hcilog_bluetrum_ts.txt", true, 0),
+        ("test_hcilog_bluetrum_ts_utf16BE.txt", true, 0),
+        ("test_hcilog_skip_chars.txt", false, 13),
+        ("test_hcilog_ignore.txt", false, 0),
+        ("test_hcilog_multiline.txt", false, 0),
+        ("test_hcilog_reboot.txt", false, 0),
+        ("test_hcilog_sco_iso.txt", false, 0),
+    ];
+
+    #[test]
+    fn cfa_file_round_trip() {
+        let packets: [(u32, &[u8]); 3] = [
+            (0x02, &[H4_CMD, 0x03, 0x0C, 0x00]),
+            (0x03, &[H4_EVT, 0x0E, 0x04, 0x01, 0x03, 0x0C, 0x00]),
+            (
+                0x01,
+                &[H4_ACL, 0x40, 0x20, 0x05, 0x00, 0x01, 0x00, 0x04, 0x00, 0x0A],
+            ),
+        ];
+        let records: Vec<BtsnoopRecord> = packets
+            .iter()
+            .enumerate()
+            .map(|(i, (flags, data))| {
+                let timestamp = BTSNOOP_EPOCH_DELTA_US + i as u64 * 1000;
+                BtsnoopRecord::from_h4(Datalink::HciUart, timestamp, *flags, data)
+            })
+            .collect();
+
+        let path = std::env::temp_dir().join("btsnoop_cfa_file_round_trip.cfa");
+        let path = path.to_string_lossy().into_owned();
+        let mut writer = BtsnoopWriter::create(&path).unwrap();
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (header, read) = read_all(&bytes);
+        assert_eq!(header, BtsnoopHeader::new(Datalink::HciUart));
+        assert_eq!(read.len(), packets.len());
+        assert_eq!(read, records);
+        for (record, (flags, data)) in read.iter().zip(packets) {
+            assert_eq!(record.flags, flags);
+            assert_eq!(record.data, data);
+            assert_eq!(record.original_len as usize, data.len());
+        }
+        assert_eq!(write_all(header.datalink, &read), bytes);
+    }
+
+    #[test]
+    fn converted_logs_round_trip() {
+        for (name, bluetrum_ts, skip_chars) in TEST_LOGS {
+            let (bytes, packets) =
+                convert_log("converted_logs_round_trip", name, bluetrum_ts, skip_chars);
+            let (header, records) = read_all(&bytes);
+            assert_eq!(header.datalink, Datalink::HciUart, "{}", name);
+            assert!(packets > 0, "{}", name);
+            assert_eq!(records.len(), packets, "{}", name);
+            assert_eq!(write_all(header.datalink, &records), bytes, "{}", name);
+
+            // 1002 的标志位与 H4 包类型一致
+            for record in &records {
+                let received = record.flags & FLAG_RECEIVED != 0;
+                assert_eq!(
+                    record.flags,
+                    flags_from_h4(record.data[0], received),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn datalink_1001_round_trip() {
+        for (name, bluetrum_ts, skip_chars) in TEST_LOGS {
+            let (bytes, _) = convert_log("datalink_1001_round_trip", name, bluetrum_ts, skip_chars);
+            let (_, records) = read_all(&bytes);
+
+            // 1002 -> 1001：去掉 H4 头，标志位不变
+            let hci: Vec<BtsnoopRecord> = records
+                .iter()
+                .map(|r| BtsnoopRecord::from_h4(Datalink::Hci, r.timestamp, r.flags, &r.data))
+                .collect();
+            let hci_bytes = write_all(Datalink::Hci, &hci);
+            let (header, hci_read) = read_all(&hci_bytes);
+            assert_eq!(header.datalink, Datalink::Hci, "{}", name);
+            assert_eq!(hci_read, hci, "{}", name);
+
+            // 1001 -> 1002：命令、事件、ACL 可以完整还原，SCO/ISO 会被当作 ACL
+            for (original, record) in records.iter().zip(&hci_read) {
+                let restored = record.to_h4(Datalink::Hci);
+                assert_eq!(restored[1..], original.data[1..], "{}", name);
+                match original.data[0] {
+                    H4_CMD | H4_ACL | H4_EVT => assert_eq!(restored, original.data, "{}", name),
+                    _ => assert_eq!(restored[0], H4_ACL, "{}", name),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flags_conventions() {
+        assert_eq!(flags_from_h4(H4_CMD, false), 0x02);
+        assert_eq!(flags_from_h4(H4_EVT, true), 0x03);
+        assert_eq!(flags_from_h4(H4_ACL, false), 0x00);
+        assert_eq!(flags_from_h4(H4_ACL, true), 0x01);
+        assert_eq!(h4_type_from_flags(0x02), H4_CMD);
+        assert_eq!(h4_type_from_flags(0x03), H4_EVT);
+        assert_eq!(h4_type_from_flags(0x00), H4_ACL);
+        assert_eq!(h4_type_from_flags(0x01), H4_ACL);
+    }
+
+    #[test]
+    fn rejects_invalid_header() {
+        let mut bytes = BtsnoopHeader::new(Datalink::HciUart).to_bytes();
+        bytes[12..].copy_from_slice(&1003u32.to_be_bytes());
+        assert!(BtsnoopHeader::parse(&bytes).is_err());
+        bytes[0] = b'x';
+        assert!(BtsnoopHeader::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_record() {
+        // 包含长度为 0xFFFFFFFF 的记录头
+        let mut header = [0u8; 24];
+        header[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = BtsnoopRecord::read(&mut &header[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated_record_ends_file() {
+        let mut bytes = write_all(
+            Datalink::HciUart,
+            &[BtsnoopRecord::from_h4(
+                Datalink::HciUart,
+                BTSNOOP_EPOCH_DELTA_US,
+                0x02,
+                &[H4_CMD, 0x03, 0x0C, 0x00],
+            )],
+        );
+        bytes.extend_from_slice(&[0, 0, 0]);
+        let (_, records) = read_all(&bytes);
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].to_h4(Datalink::HciUart),
+            [H4_CMD, 0x03, 0x0C, 0x00]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// 安卓 bugreport 中的 btsnooz 抓包
+//
+// bugreport 的蓝牙 dumpsys 部分包含一段 base64 文本：
+//   --- BEGIN:BTSNOOP_LOG_SUMMARY (xxx bytes in) ---
+//   ...
+//   --- END:BTSNOOP_LOG_SUMMARY ---
+// 解码后为 9 字节前导（版本号、最后一条记录的时间，小端）加 zlib 压缩的记录。
+// 每条记录为记录头加数据，记录头中的长度包含 1 字节的类型：
+// - 版本 1：长度(2)、与上一条记录的时间差(4)、类型(1)；
+// - 版本 2：长度(2)、原始长度(2)、时间差(4)、类型(1)。
+// 时间和时间差的单位都是微秒（安卓源码中的字段名为 last_timestamp_ms、delta_time_ms，但填入的是微秒）。
+
+use std::io::{self, ErrorKind, Read};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::{DecodeError, Engine};
+use flate2::read::ZlibDecoder;
+use serde::Serialize;
+
+use super::btsnoop::{flags_from_h4, BtsnoopRecord, BtsnoopWriter, BTSNOOP_EPOCH_DELTA_US};
+use super::packet::PacketType;
+use super::writer::CaptureWriter;
+use super::{check_output, output_path, split_extension, HciLogOptions, OutputFormat};
+use crate::utils::process_ascii_lines_from_file;
+
+const BEGIN_MARKER: &str = "BEGIN:BTSNOOP_LOG_SUMMARY";
+const END_MARKER: &str = "END:BTSNOOP_LOG_SUMMARY";
+
+// 前导长度：版本号(1) + 最后一条记录的时间(8)
+const PREAMBLE_LEN: usize = 9;
+
+/// btsnooz 提取结果
+#[derive(Serialize, Debug, Default)]
+pub struct BtsnoozReport {
+    pub output_files: Vec<String>,  // 每段 btsnooz 输出一个文件
+    pub packets: usize,             // 写入的数据包数
+    pub truncated: usize,           // 被安卓截断的数据包数（只保留了头部）
+    pub skipped: usize,             // 未知类型的记录数
+    pub incomplete_sections: usize, // 不完整的 btsnooz 段数（bugreport 被截断），只保留了之前的记录
+}
+
+/// 从 bugreport 文本中提取 btsnooz，解压后写入 BTSnoop 文件
+///
+/// 输出路径、扩展名和覆盖规则与 `parse_hci_log` 相同，输出格式固定为 BTSnoop。
+/// bugreport 中有多段 btsnooz 时，之后的文件在扩展名前加 `_2`、`_3`……
+pub fn extract_btsnooz_do(file_path: &str, options: &HciLogOptions) -> io::Result<BtsnoozReport> {
+    let sections = read_sections(file_path)?;
+    if sections.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("No btsnooz section found in {}", file_path),
+        ));
+    }
+
+    let mut report = BtsnoozReport::default();
+    let mut captures = Vec::with_capacity(sections.len());
+    for (section, complete) in &sections {
+        captures.push(decode_section(section, *complete, &mut report)?);
+    }
+
+    // 先检查所有输出文件，避免只写入了一部分
+    let options = HciLogOptions {
+        output_format: OutputFormat::Btsnoop,
+        ..options.clone()
+    };
+    let output = output_path(file_path, &options, 1);
+    let outputs: Vec<String> = (1..=captures.len())
+        .map(|index| match index {
+            1 => output.clone(),
+            _ => {
+                let (base, extension) = split_extension(&output);
+                format!("{}_{}{}", base, index, extension)
+            }
+        })
+        .collect();
+    for path in &outputs {
+        check_output(file_path, path, options.overwrite)?;
+    }
+
+    for (records, path) in captures.iter().zip(outputs) {
+        let mut writer = BtsnoopWriter::create(&path)?;
+        for record in records {
+            writer.write_record(record)?;
+        }
+        writer.flush()?;
+        report.output_files.push(path);
+    }
+    Ok(report)
+}
+
+// 取出每段 btsnooz 的 base64 文本（去掉空白）以及是否读到了结束标记
+fn read_sections(file_path: &str) -> io::Result<Vec<(String, bool)>> {
+    let mut sections = Vec::new();
+    let mut current: Option<String> = None;
+    process_ascii_lines_from_file(file_path, |line| {
+        if let Some(section) = current.as_mut() {
+            if line.contains(END_MARKER) {
+                sections.extend(current.take().map(|s| (s, true)));
+            } else {
+                section.extend(line.chars().filter(|c| !c.is_whitespace()));
+            }
+        } else if line.contains(BEGIN_MARKER) {
+            current = Some(String::new());
+        }
+        true
+    })?;
+    // 没有结束标记时（bugreport 被截断）保留已读到的内容
+    sections.extend(current.map(|s| (s, false)));
+    Ok(sections)
+}
+
+// 解码一段 btsnooz 的 base64 文本
+// 文本不完整时只解码有效的部分，并保留解压出的完整记录
+fn decode_section(
+    text: &str,
+    complete: bool,
+    report: &mut BtsnoozReport,
+) -> io::Result<Vec<BtsnoopRecord>> {
+    let (snooz, base64_complete) = decode_base64_prefix(text.as_bytes());
+    let (records, zlib_complete) = decode_snooz(&snooz, report)?;
+    if !(complete && base64_complete && zlib_complete) {
+        report.incomplete_sections += 1;
+    }
+    Ok(records)
+}
+
+// 解码 base64 文本中最长的有效前缀，返回解码结果以及整段文本是否有效
+fn decode_base64_prefix(text: &[u8]) -> (Vec<u8>, bool) {
+    let mut end = text.len();
+    loop {
+        match STANDARD.decode(&text[..end]) {
+            Ok(data) => return (data, end == text.len()),
+            // 从出错的 4 字符组之前截断
+            Err(
+                DecodeError::InvalidByte(offset, _) | DecodeError::InvalidLastSymbol(offset, _),
+            ) => {
+                end = offset / 4 * 4;
+            }
+            // 长度或填充不对：去掉最后一个不完整的（或最后一个）4 字符组
+            Err(_) => end = (end - 1) / 4 * 4,
+        }
+    }
+}
+
+// 逐块解压，压缩数据不完整或损坏时保留已解压的部分，返回解压结果以及压缩数据是否完整
+fn inflate(compressed: &[u8]) -> (Vec<u8>, bool) {
+    let mut decoder = ZlibDecoder::new(compressed);
+    let mut data = Vec::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match decoder.read(&mut buf) {
+            Ok(0) => return (data, true),
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return (data, false),
+        }
+    }
+}
+
+// 解码一段 btsnooz，返回数据链路类型为 1002 的记录以及压缩数据是否完整
+fn decode_snooz(
+    snooz: &[u8],
+    report: &mut BtsnoozReport,
+) -> io::Result<(Vec<BtsnoopRecord>, bool)> {
+    if snooz.len() < PREAMBLE_LEN {
+        return Err(invalid_data("btsnooz section is too short".to_string()));
+    }
+    let version = snooz[0];
+    let header_len = match version {
+        1 => 7,
+        2 => 9,
+        _ => {
+            return Err(invalid_data(format!(
+                "Unsupported btsnooz version {}",
+                version
+            )))
+        }
+    };
+    let last_timestamp_us = u64::from_le_bytes(snooz[1..PREAMBLE_LEN].try_into().unwrap());
+
+    let (data, complete) = inflate(&snooz[PREAMBLE_LEN..]);
+
+    // 记录头：(长度, 原始长度, 时间差, 类型)，长度均包含类型字节
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + header_len <= data.len() {
+        let header = &data[offset..offset + header_len];
+        let read_u16 = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]) as usize;
+        let read_u32 =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let (length, original_len, delta_us, snooz_type) = match version {
+            1 => (read_u16(0), read_u16(0), read_u32(2), header[6]),
+            _ => (read_u16(0), read_u16(2), read_u32(4), header[8]),
+        };
+        let start = offset + header_len;
+        let end = start + length.saturating_sub(1);
+        if end > data.len() {
+            // 最后一条记录不完整，丢弃
+            break;
+        }
+        entries.push((original_len, delta_us, snooz_type, &data[start..end]));
+        offset = end;
+    }
+
+    // 前导中是最后一条记录的时间（公元 1970 年起的微秒数），倒推第一条记录的时间
+    // 数据不完整时缺少之后记录的时间差，得到的时间会偏晚
+    let total_delta_us: u64 = entries.iter().map(|e| e.1 as u64).sum();
+    let mut timestamp_us = last_timestamp_us.saturating_sub(total_delta_us);
+
+    let mut records = Vec::with_capacity(entries.len());
+    for (original_len, delta_us, snooz_type, payload) in entries {
+        timestamp_us += delta_us as u64;
+        let Some((packet_type, received)) = snooz_packet_type(snooz_type) else {
+            report.skipped += 1;
+            continue;
+        };
+
+        let h4_type = packet_type.h4_type();
+        let mut data = Vec::with_capacity(payload.len() + 1);
+        data.push(h4_type);
+        data.extend_from_slice(payload);
+        if original_len > data.len() {
+            report.truncated += 1;
+        }
+        records.push(BtsnoopRecord {
+            original_len: original_len.max(data.len()) as u32,
+            flags: flags_from_h4(h4_type, received),
+            drops: 0,
+            timestamp: timestamp_us + BTSNOOP_EPOCH_DELTA_US,
+            data,
+        });
+    }
+    report.packets += records.len();
+    Ok((records, complete))
+}
+
+// btsnooz 记录类型：高 4 位为方向（0x1: 接收，0x2: 发送）
+fn snooz_packet_type(snooz_type: u8) -> Option<(PacketType, bool)> {
+    match snooz_type {
+        0x10 => Some((PacketType::Evt, true)),
+        0x11 => Some((PacketType::Acl, true)),
+        0x12 => Some((PacketType::Sco, true)),
+        0x17 => Some((PacketType::Iso, true)),
+        0x20 => Some((PacketType::Cmd, false)),
+        0x21 => Some((PacketType::Acl, false)),
+        0x22 => Some((PacketType::Sco, false)),
+        0x2d => Some((PacketType::Iso, false)),
+        _ => None,
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    const LAST_TIMESTAMP_US: u64 = 1_700_000_000_123_456;
+
+    // 生成版本 2 的 btsnooz 文本，记录为 (时间差, 类型, 数据)
+    fn encode_snooz(entries: &[(u32, u8, Vec<u8>)]) -> String {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for (delta_us, snooz_type, payload) in entries {
+            let length = (payload.len() + 1) as u16;
+            encoder.write_all(&length.to_le_bytes()).unwrap();
+            encoder.write_all(&length.to_le_bytes()).unwrap();
+            encoder.write_all(&delta_us.to_le_bytes()).unwrap();
+            encoder.write_all(&[*snooz_type]).unwrap();
+            encoder.write_all(payload).unwrap();
+        }
+        let mut snooz = vec![2];
+        snooz.extend_from_slice(&LAST_TIMESTAMP_US.to_le_bytes());
+        snooz.extend(encoder.finish().unwrap());
+        STANDARD.encode(snooz)
+    }
+
+    #[test]
+    fn timestamps_are_microseconds() {
+        let text = encode_snooz(&[
+            (0, 0x20, vec![0x03, 0x0C, 0x00]),
+            (1500, 0x10, vec![0x0E, 0x04, 0x01, 0x03, 0x0C, 0x00]),
+        ]);
+        let mut report = BtsnoozReport::default();
+        let records = decode_section(&text, true, &mut report).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(report.incomplete_sections, 0);
+        assert_eq!(
+            records[0].timestamp,
+            LAST_TIMESTAMP_US - 1500 + BTSNOOP_EPOCH_DELTA_US
+        );
+        assert_eq!(
+            records[1].timestamp,
+            LAST_TIMESTAMP_US + BTSNOOP_EPOCH_DELTA_US
+        );
+        assert_eq!(records[0].data, [0x01, 0x03, 0x0C, 0x00]);
+        assert_eq!(records[1].data, [0x04, 0x0E, 0x04, 0x01, 0x03, 0x0C, 0x00]);
+    }
+
+    #[test]
+    fn truncated_section_keeps_leading_records() {
+        // 伪随机负载，避免压缩后过短
+        let mut seed = 1u32;
+        let entries: Vec<(u32, u8, Vec<u8>)> = (0..200)
+            .map(|i| {
+                let payload = (0..40)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                        (seed >> 16) as u8
+                    })
+                    .collect();
+                (i * 10, 0x11, payload)
+            })
+            .collect();
+        let text = encode_snooz(&entries);
+        let full = decode_section(&text, true, &mut BtsnoozReport::default()).unwrap();
+        assert_eq!(full.len(), entries.len());
+
+        let mut longest = 0;
+        for end in (12..text.len()).step_by(7) {
+            let mut report = BtsnoozReport::default();
+            let records = decode_section(&text[..end], false, &mut report).unwrap();
+            assert_eq!(report.incomplete_sections, 1);
+            assert!(records.len() < full.len());
+            for (record, expected) in records.iter().zip(&full) {
+                assert_eq!(record.data, expected.data);
+            }
+            longest = longest.max(records.len());
+        }
+        assert!(longest > entries.len() / 2);
+    }
+
+    #[test]
+    fn invalid_base64_tail_is_ignored() {
+        let text = encode_snooz(&[(0, 0x20, vec![0x03, 0x0C, 0x00])]);
+        let mut report = BtsnoozReport::default();
+        let records = decode_section(&format!("{}!!", text), true, &mut report).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(report.incomplete_sections, 1);
+    }
+}
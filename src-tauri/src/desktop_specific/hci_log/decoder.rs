@@ -0,0 +1,627 @@
+use super::packet::PacketType;
+
+/// 生成一个 HCI 数据包的可读摘要，`data` 不含 H4 头
+pub fn summarize(packet_type: PacketType, data: &[u8]) -> String {
+    match packet_type {
+        PacketType::Cmd => summarize_command(data),
+        PacketType::Evt => summarize_event(data),
+        PacketType::Acl => summarize_acl(data),
+        PacketType::Sco => match (read_u16(data, 0), data.get(2)) {
+            (Some(handle), Some(len)) => {
+                format!("SCO handle=0x{:04x} len={}", handle & 0x0FFF, len)
+            }
+            _ => "SCO (truncated)".to_string(),
+        },
+        PacketType::Iso => match (read_u16(data, 0), read_u16(data, 2)) {
+            (Some(handle), Some(len)) => {
+                format!("ISO handle=0x{:04x} len={}", handle & 0x0FFF, len & 0x3FFF)
+            }
+            _ => "ISO (truncated)".to_string(),
+        },
+    }
+}
+
+// 参数以连接句柄开头的命令
+const HANDLE_COMMANDS: [u16; 18] = [
+    0x0411, 0x0413, 0x041B, 0x041C, 0x041D, 0x0428, 0x043D, 0x0803, 0x0804, 0x0809, 0x1405, 0x2013,
+    0x2016, 0x2019, 0x201A, 0x201B, 0x2022, 0x2032,
+];
+
+// 参数以 status(1) handle(2) 开头的事件
+pub(super) const STATUS_HANDLE_EVENTS: [u8; 8] = [0x06, 0x08, 0x0B, 0x0C, 0x23, 0x2C, 0x2D, 0x30];
+
+fn summarize_command(data: &[u8]) -> String {
+    let opcode = match read_u16(data, 0) {
+        Some(opcode) => opcode,
+        None => return "Command (truncated)".to_string(),
+    };
+    let params = data.get(3..).unwrap_or_default();
+
+    let mut summary = opcode_name(opcode);
+    match opcode {
+        // Disconnect
+        0x0406 => {
+            if let (Some(handle), Some(&reason)) = (read_u16(params, 0), params.get(2)) {
+                summary.push_str(&format!(
+                    " handle=0x{:04x} reason={}",
+                    handle & 0x0FFF,
+                    status_name(reason)
+                ));
+            }
+        }
+        // 参数以 BD_ADDR 开头的命令
+        0x0405 | 0x0408 | 0x0409 | 0x040A | 0x040B | 0x040C | 0x040D | 0x040E | 0x0419 | 0x042B
+        | 0x042C | 0x042D | 0x0434 | 0x080B => {
+            if let Some(addr) = params.get(0..6) {
+                summary.push_str(&format!(" addr={}", format_bd_addr(addr)));
+            }
+        }
+        // 参数以连接句柄开头的命令
+        _ if HANDLE_COMMANDS.contains(&opcode) => {
+            if let Some(handle) = read_u16(params, 0) {
+                summary.push_str(&format!(" handle=0x{:04x}", handle & 0x0FFF));
+            }
+        }
+        // LE_Set_Advertising_Enable / LE_Set_Scan_Enable
+        0x200A | 0x200C => {
+            if let Some(&enable) = params.first() {
+                summary.push_str(if enable != 0 { " enable" } else { " disable" });
+            }
+        }
+        _ => {}
+    }
+    summary
+}
+
+fn summarize_event(data: &[u8]) -> String {
+    let code = match data.first() {
+        Some(&code) => code,
+        None => return "Event (truncated)".to_string(),
+    };
+    let params = data.get(2..).unwrap_or_default();
+
+    match code {
+        // Command_Complete: num_packets(1) opcode(2) status(1)
+        0x0E => match read_u16(params, 1) {
+            Some(opcode) => {
+                let mut summary = format!("Command_Complete {}", opcode_name(opcode));
+                if let Some(&status) = params.get(3) {
+                    summary.push_str(&format!(" status={}", status_name(status)));
+                }
+                summary
+            }
+            None => "Command_Complete (truncated)".to_string(),
+        },
+        // Command_Status: status(1) num_packets(1) opcode(2)
+        0x0F => match (params.first(), read_u16(params, 2)) {
+            (Some(&status), Some(opcode)) => format!(
+                "Command_Status {} status={}",
+                opcode_name(opcode),
+                status_name(status)
+            ),
+            _ => "Command_Status (truncated)".to_string(),
+        },
+        // Connection_Complete: status(1) handle(2) addr(6) link_type(1)
+        0x03 => {
+            let mut summary = "Connection_Complete".to_string();
+            push_status_handle(&mut summary, params);
+            if let Some(addr) = params.get(3..9) {
+                summary.push_str(&format!(" addr={}", format_bd_addr(addr)));
+            }
+            summary
+        }
+        // Disconnection_Complete: status(1) handle(2) reason(1)
+        0x05 => {
+            let mut summary = "Disconnection_Complete".to_string();
+            push_status_handle(&mut summary, params);
+            if let Some(&reason) = params.get(3) {
+                summary.push_str(&format!(" reason={}", status_name(reason)));
+            }
+            summary
+        }
+        // 参数为 status(1) handle(2) 开头的事件
+        _ if STATUS_HANDLE_EVENTS.contains(&code) => {
+            let mut summary = event_name(code);
+            push_status_handle(&mut summary, params);
+            summary
+        }
+        // Number_Of_Completed_Packets: num_handles(1) [handle(2) count(2)]...
+        0x13 => {
+            let mut summary = "Number_Of_Completed_Packets".to_string();
+            let num_handles = params.first().copied().unwrap_or(0) as usize;
+            for i in 0..num_handles {
+                if let (Some(handle), Some(count)) =
+                    (read_u16(params, 1 + i * 4), read_u16(params, 3 + i * 4))
+                {
+                    summary.push_str(&format!(" 0x{:04x}:{}", handle & 0x0FFF, count));
+                }
+            }
+            summary
+        }
+        // 参数以 BD_ADDR 开头的事件
+        0x04 | 0x16 | 0x17 | 0x18 | 0x31 | 0x32 | 0x33 | 0x34 => {
+            let mut summary = event_name(code);
+            if let Some(addr) = params.get(0..6) {
+                summary.push_str(&format!(" addr={}", format_bd_addr(addr)));
+            }
+            summary
+        }
+        0x3E => summarize_le_meta(params),
+        _ => event_name(code),
+    }
+}
+
+fn summarize_le_meta(params: &[u8]) -> String {
+    let subevent = match params.first() {
+        Some(&subevent) => subevent,
+        None => return "LE_Meta (truncated)".to_string(),
+    };
+    let params = &params[1..];
+
+    let mut summary = le_subevent_name(subevent);
+    match subevent {
+        // LE_Connection_Complete / LE_Enhanced_Connection_Complete:
+        // status(1) handle(2) role(1) peer_addr_type(1) peer_addr(6)
+        0x01 | 0x0A => {
+            push_status_handle(&mut summary, params);
+            if let Some(&role) = params.get(3) {
+                summary.push_str(if role == 0 {
+                    " role=central"
+                } else {
+                    " role=peripheral"
+                });
+            }
+            if let Some(addr) = params.get(5..11) {
+                summary.push_str(&format!(" peer={}", format_bd_addr(addr)));
+            }
+        }
+        // LE_Advertising_Report / LE_Extended_Advertising_Report: num_reports(1)
+        0x02 | 0x0D => {
+            if let Some(&num) = params.first() {
+                summary.push_str(&format!(" reports={}", num));
+            }
+        }
+        // LE_Connection_Update_Complete: status(1) handle(2) interval(2) latency(2) timeout(2)
+        0x03 => {
+            push_status_handle(&mut summary, params);
+            if let Some(interval) = read_u16(params, 3) {
+                summary.push_str(&format!(" interval={:.2}ms", interval as f64 * 1.25));
+            }
+        }
+        // 参数以 status(1) handle(2) 开头的子事件
+        0x04 | 0x0C | 0x19 => push_status_handle(&mut summary, params),
+        // 参数以 handle(2) 开头的子事件
+        0x05..=0x07 => {
+            if let Some(handle) = read_u16(params, 0) {
+                summary.push_str(&format!(" handle=0x{:04x}", handle & 0x0FFF));
+            }
+        }
+        _ => {}
+    }
+    summary
+}
+
+fn summarize_acl(data: &[u8]) -> String {
+    let (handle, len) = match (read_u16(data, 0), read_u16(data, 2)) {
+        (Some(handle), Some(len)) => (handle, len),
+        _ => return "ACL (truncated)".to_string(),
+    };
+
+    // PB 标志：0b00/0b10 为 L2CAP 起始分片，0b01 为后续分片
+    let pb = (handle >> 12) & 0x03;
+    let mut summary = format!("ACL handle=0x{:04x} len={}", handle & 0x0FFF, len);
+    if pb == 0x01 {
+        summary.push_str(" continuation");
+    } else if let (Some(l2cap_len), Some(cid)) = (read_u16(data, 4), read_u16(data, 6)) {
+        summary.push_str(&format!(" L2CAP cid=0x{:04x} len={}", cid, l2cap_len));
+    }
+    summary
+}
+
+/// 数据包涉及的连接句柄（不含 PB/BC 等标志位），`data` 不含 H4 头
+///
+/// 数据包取头部的句柄，命令和事件只识别参数中带句柄的常见类型，
+/// Number_Of_Completed_Packets 可能包含多个句柄。
+pub fn connection_handles(packet_type: PacketType, data: &[u8]) -> Vec<u16> {
+    let handle_at =
+        |params: &[u8], offset: usize| read_u16(params, offset).map(|handle| handle & 0x0FFF);
+    match packet_type {
+        PacketType::Acl | PacketType::Sco | PacketType::Iso => {
+            handle_at(data, 0).into_iter().collect()
+        }
+        PacketType::Cmd => {
+            let params = data.get(3..).unwrap_or_default();
+            match read_u16(data, 0) {
+                Some(opcode) if opcode == 0x0406 || HANDLE_COMMANDS.contains(&opcode) => {
+                    handle_at(params, 0).into_iter().collect()
+                }
+                _ => Vec::new(),
+            }
+        }
+        PacketType::Evt => {
+            let params = data.get(2..).unwrap_or_default();
+            match data.first().copied() {
+                Some(code)
+                    if code == 0x03 || code == 0x05 || STATUS_HANDLE_EVENTS.contains(&code) =>
+                {
+                    handle_at(params, 1).into_iter().collect()
+                }
+                // Number_Of_Completed_Packets: num_handles(1) [handle(2) count(2)]...
+                Some(0x13) => {
+                    let num_handles = params.first().copied().unwrap_or(0) as usize;
+                    (0..num_handles)
+                        .filter_map(|i| handle_at(params, 1 + i * 4))
+                        .collect()
+                }
+                // LE Meta，子事件参数从 params[1] 开始
+                Some(0x3E) => match params.first() {
+                    Some(0x01 | 0x0A | 0x03 | 0x04 | 0x0C | 0x19) => {
+                        handle_at(params, 2).into_iter().collect()
+                    }
+                    Some(0x05..=0x07) => handle_at(params, 1).into_iter().collect(),
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+fn push_status_handle(summary: &mut String, params: &[u8]) {
+    if let Some(&status) = params.first() {
+        summary.push_str(&format!(" status={}", status_name(status)));
+    }
+    if let Some(handle) = read_u16(params, 1) {
+        summary.push_str(&format!(" handle=0x{:04x}", handle & 0x0FFF));
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+/// BD_ADDR 按小端存储，显示时倒序
+pub fn format_bd_addr(addr: &[u8]) -> String {
+    addr.iter()
+        .rev()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 命令名称，未知命令显示 OGF/OCF
+pub fn opcode_name(opcode: u16) -> String {
+    let name = match opcode {
+        // Link Control (OGF 0x01)
+        0x0401 => "Inquiry",
+        0x0402 => "Inquiry_Cancel",
+        0x0405 => "Create_Connection",
+        0x0406 => "Disconnect",
+        0x0408 => "Create_Connection_Cancel",
+        0x0409 => "Accept_Connection_Request",
+        0x040A => "Reject_Connection_Request",
+        0x040B => "Link_Key_Request_Reply",
+        0x040C => "Link_Key_Request_Negative_Reply",
+        0x040D => "PIN_Code_Request_Reply",
+        0x040E => "PIN_Code_Request_Negative_Reply",
+        0x0411 => "Authentication_Requested",
+        0x0413 => "Set_Connection_Encryption",
+        0x0419 => "Remote_Name_Request",
+        0x041B => "Read_Remote_Supported_Features",
+        0x041C => "Read_Remote_Extended_Features",
+        0x041D => "Read_Remote_Version_Information",
+        0x0428 => "Setup_Synchronous_Connection",
+        0x0429 => "Accept_Synchronous_Connection_Request",
+        0x042B => "IO_Capability_Request_Reply",
+        0x042C => "User_Confirmation_Request_Reply",
+        0x042D => "User_Confirmation_Request_Negative_Reply",
+        0x0434 => "IO_Capability_Request_Negative_Reply",
+        0x043D => "Enhanced_Setup_Synchronous_Connection",
+        0x043E => "Enhanced_Accept_Synchronous_Connection_Request",
+        // Link Policy (OGF 0x02)
+        0x0803 => "Sniff_Mode",
+        0x0804 => "Exit_Sniff_Mode",
+        0x0809 => "Role_Discovery",
+        0x080B => "Switch_Role",
+        0x080D => "Write_Link_Policy_Settings",
+        0x080F => "Write_Default_Link_Policy_Settings",
+        0x0811 => "Sniff_Subrating",
+        // Controller & Baseband (OGF 0x03)
+        0x0C01 => "Set_Event_Mask",
+        0x0C03 => "Reset",
+        0x0C05 => "Set_Event_Filter",
+        0x0C0D => "Read_Stored_Link_Key",
+        0x0C12 => "Delete_Stored_Link_Key",
+        0x0C13 => "Write_Local_Name",
+        0x0C14 => "Read_Local_Name",
+        0x0C16 => "Write_Connection_Accept_Timeout",
+        0x0C18 => "Write_Page_Timeout",
+        0x0C1A => "Write_Scan_Enable",
+        0x0C1C => "Write_Page_Scan_Activity",
+        0x0C1E => "Write_Inquiry_Scan_Activity",
+        0x0C20 => "Write_Authentication_Enable",
+        0x0C23 => "Read_Class_Of_Device",
+        0x0C24 => "Write_Class_Of_Device",
+        0x0C26 => "Write_Voice_Setting",
+        0x0C2D => "Read_Transmit_Power_Level",
+        0x0C31 => "Set_Controller_To_Host_Flow_Control",
+        0x0C33 => "Host_Buffer_Size",
+        0x0C35 => "Host_Number_Of_Completed_Packets",
+        0x0C45 => "Write_Inquiry_Mode",
+        0x0C46 => "Read_Page_Scan_Type",
+        0x0C47 => "Write_Page_Scan_Type",
+        0x0C52 => "Write_Extended_Inquiry_Response",
+        0x0C56 => "Write_Simple_Pairing_Mode",
+        0x0C63 => "Set_Event_Mask_Page_2",
+        0x0C6D => "Write_LE_Host_Support",
+        0x0C7A => "Write_Secure_Connections_Host_Support",
+        // Informational (OGF 0x04)
+        0x1001 => "Read_Local_Version_Information",
+        0x1002 => "Read_Local_Supported_Commands",
+        0x1003 => "Read_Local_Supported_Features",
+        0x1004 => "Read_Local_Extended_Features",
+        0x1005 => "Read_Buffer_Size",
+        0x1009 => "Read_BD_ADDR",
+        // Status (OGF 0x05)
+        0x1403 => "Read_Link_Quality",
+        0x1405 => "Read_RSSI",
+        // LE Controller (OGF 0x08)
+        0x2001 => "LE_Set_Event_Mask",
+        0x2002 => "LE_Read_Buffer_Size",
+        0x2003 => "LE_Read_Local_Supported_Features",
+        0x2005 => "LE_Set_Random_Address",
+        0x2006 => "LE_Set_Advertising_Parameters",
+        0x2007 => "LE_Read_Advertising_Physical_Channel_Tx_Power",
+        0x2008 => "LE_Set_Advertising_Data",
+        0x2009 => "LE_Set_Scan_Response_Data",
+        0x200A => "LE_Set_Advertising_Enable",
+        0x200B => "LE_Set_Scan_Parameters",
+        0x200C => "LE_Set_Scan_Enable",
+        0x200D => "LE_Create_Connection",
+        0x200E => "LE_Create_Connection_Cancel",
+        0x200F => "LE_Read_Filter_Accept_List_Size",
+        0x2010 => "LE_Clear_Filter_Accept_List",
+        0x2011 => "LE_Add_Device_To_Filter_Accept_List",
+        0x2013 => "LE_Connection_Update",
+        0x2014 => "LE_Set_Host_Channel_Classification",
+        0x2016 => "LE_Read_Remote_Features",
+        0x2017 => "LE_Encrypt",
+        0x2018 => "LE_Rand",
+        0x2019 => "LE_Enable_Encryption",
+        0x201A => "LE_Long_Term_Key_Request_Reply",
+        0x201B => "LE_Long_Term_Key_Request_Negative_Reply",
+        0x201C => "LE_Read_Supported_States",
+        0x2020 => "LE_Remote_Connection_Parameter_Request_Reply",
+        0x2022 => "LE_Set_Data_Length",
+        0x2023 => "LE_Read_Suggested_Default_Data_Length",
+        0x2024 => "LE_Write_Suggested_Default_Data_Length",
+        0x2027 => "LE_Add_Device_To_Resolving_List",
+        0x2029 => "LE_Clear_Resolving_List",
+        0x202D => "LE_Set_Address_Resolution_Enable",
+        0x202F => "LE_Read_Maximum_Data_Length",
+        0x2030 => "LE_Read_PHY",
+        0x2031 => "LE_Set_Default_PHY",
+        0x2032 => "LE_Set_PHY",
+        0x2036 => "LE_Set_Extended_Advertising_Parameters",
+        0x2037 => "LE_Set_Extended_Advertising_Data",
+        0x2038 => "LE_Set_Extended_Scan_Response_Data",
+        0x2039 => "LE_Set_Extended_Advertising_Enable",
+        0x2041 => "LE_Set_Extended_Scan_Parameters",
+        0x2042 => "LE_Set_Extended_Scan_Enable",
+        0x2043 => "LE_Extended_Create_Connection",
+        0x2060 => "LE_Read_Buffer_Size_V2",
+        0x2062 => "LE_Set_CIG_Parameters",
+        0x2064 => "LE_Create_CIS",
+        0x2065 => "LE_Remove_CIG",
+        0x2066 => "LE_Accept_CIS_Request",
+        0x2068 => "LE_Create_BIG",
+        0x206E => "LE_Setup_ISO_Data_Path",
+        0x206F => "LE_Remove_ISO_Data_Path",
+        _ => {
+            let ogf = opcode >> 10;
+            let ocf = opcode & 0x03FF;
+            return if ogf == 0x3F {
+                format!("Vendor_Command(OCF 0x{:03x})", ocf)
+            } else {
+                format!("Command(OGF 0x{:02x} OCF 0x{:03x})", ogf, ocf)
+            };
+        }
+    };
+    name.to_string()
+}
+
+/// 事件名称
+pub fn event_name(code: u8) -> String {
+    let name = match code {
+        0x01 => "Inquiry_Complete",
+        0x02 => "Inquiry_Result",
+        0x03 => "Connection_Complete",
+        0x04 => "Connection_Request",
+        0x05 => "Disconnection_Complete",
+        0x06 => "Authentication_Complete",
+        0x07 => "Remote_Name_Request_Complete",
+        0x08 => "Encryption_Change",
+        0x0B => "Read_Remote_Supported_Features_Complete",
+        0x0C => "Read_Remote_Version_Information_Complete",
+        0x0E => "Command_Complete",
+        0x0F => "Command_Status",
+        0x10 => "Hardware_Error",
+        0x12 => "Role_Change",
+        0x13 => "Number_Of_Completed_Packets",
+        0x14 => "Mode_Change",
+        0x16 => "PIN_Code_Request",
+        0x17 => "Link_Key_Request",
+        0x18 => "Link_Key_Notification",
+        0x1A => "Data_Buffer_Overflow",
+        0x1B => "Max_Slots_Change",
+        0x22 => "Inquiry_Result_With_RSSI",
+        0x23 => "Read_Remote_Extended_Features_Complete",
+        0x2C => "Synchronous_Connection_Complete",
+        0x2D => "Synchronous_Connection_Changed",
+        0x2F => "Extended_Inquiry_Result",
+        0x30 => "Encryption_Key_Refresh_Complete",
+        0x31 => "IO_Capability_Request",
+        0x32 => "IO_Capability_Response",
+        0x33 => "User_Confirmation_Request",
+        0x34 => "User_Passkey_Request",
+        0x36 => "Simple_Pairing_Complete",
+        0x3E => "LE_Meta",
+        0x57 => "Authenticated_Payload_Timeout_Expired",
+        0xFF => "Vendor_Specific",
+        _ => return format!("Event(0x{:02x})", code),
+    };
+    name.to_string()
+}
+
+/// LE Meta 子事件名称
+pub fn le_subevent_name(subevent: u8) -> String {
+    let name = match subevent {
+        0x01 => "LE_Connection_Complete",
+        0x02 => "LE_Advertising_Report",
+        0x03 => "LE_Connection_Update_Complete",
+        0x04 => "LE_Read_Remote_Features_Complete",
+        0x05 => "LE_Long_Term_Key_Request",
+        0x06 => "LE_Remote_Connection_Parameter_Request",
+        0x07 => "LE_Data_Length_Change",
+        0x08 => "LE_Read_Local_P256_Public_Key_Complete",
+        0x09 => "LE_Generate_DHKey_Complete",
+        0x0A => "LE_Enhanced_Connection_Complete",
+        0x0C => "LE_PHY_Update_Complete",
+        0x0D => "LE_Extended_Advertising_Report",
+        0x12 => "LE_Advertising_Set_Terminated",
+        0x14 => "LE_Channel_Selection_Algorithm",
+        0x19 => "LE_CIS_Established",
+        0x1A => "LE_CIS_Request",
+        0x1B => "LE_Create_BIG_Complete",
+        _ => return format!("LE_Meta(0x{:02x})", subevent),
+    };
+    name.to_string()
+}
+
+/// HCI 错误码（状态、断开原因）名称
+pub fn status_name(code: u8) -> String {
+    let name = match code {
+        0x00 => "Success",
+        0x01 => "Unknown_HCI_Command",
+        0x02 => "Unknown_Connection_Identifier",
+        0x03 => "Hardware_Failure",
+        0x04 => "Page_Timeout",
+        0x05 => "Authentication_Failure",
+        0x06 => "PIN_Or_Key_Missing",
+        0x07 => "Memory_Capacity_Exceeded",
+        0x08 => "Connection_Timeout",
+        0x09 => "Connection_Limit_Exceeded",
+        0x0A => "Synchronous_Connection_Limit_Exceeded",
+        0x0B => "Connection_Already_Exists",
+        0x0C => "Command_Disallowed",
+        0x0D => "Connection_Rejected_Limited_Resources",
+        0x0E => "Connection_Rejected_Security_Reasons",
+        0x0F => "Connection_Rejected_Unacceptable_BD_ADDR",
+        0x10 => "Connection_Accept_Timeout_Exceeded",
+        0x11 => "Unsupported_Feature_Or_Parameter_Value",
+        0x12 => "Invalid_HCI_Command_Parameters",
+        0x13 => "Remote_User_Terminated_Connection",
+        0x14 => "Remote_Device_Terminated_Low_Resources",
+        0x15 => "Remote_Device_Terminated_Power_Off",
+        0x16 => "Connection_Terminated_By_Local_Host",
+        0x17 => "Repeated_Attempts",
+        0x18 => "Pairing_Not_Allowed",
+        0x19 => "Unknown_LMP_PDU",
+        0x1A => "Unsupported_Remote_Feature",
+        0x1B => "SCO_Offset_Rejected",
+        0x1C => "SCO_Interval_Rejected",
+        0x1D => "SCO_Air_Mode_Rejected",
+        0x1E => "Invalid_LMP_LL_Parameters",
+        0x1F => "Unspecified_Error",
+        0x20 => "Unsupported_LMP_LL_Parameter_Value",
+        0x21 => "Role_Change_Not_Allowed",
+        0x22 => "LMP_LL_Response_Timeout",
+        0x23 => "LMP_Error_Transaction_Collision",
+        0x24 => "LMP_PDU_Not_Allowed",
+        0x25 => "Encryption_Mode_Not_Acceptable",
+        0x26 => "Link_Key_Cannot_Be_Changed",
+        0x27 => "Requested_QoS_Not_Supported",
+        0x28 => "Instant_Passed",
+        0x29 => "Pairing_With_Unit_Key_Not_Supported",
+        0x2A => "Different_Transaction_Collision",
+        0x2C => "QoS_Unacceptable_Parameter",
+        0x2D => "QoS_Rejected",
+        0x2E => "Channel_Classification_Not_Supported",
+        0x2F => "Insufficient_Security",
+        0x30 => "Parameter_Out_Of_Mandatory_Range",
+        0x32 => "Role_Switch_Pending",
+        0x34 => "Reserved_Slot_Violation",
+        0x35 => "Role_Switch_Failed",
+        0x36 => "Extended_Inquiry_Response_Too_Large",
+        0x37 => "Secure_Simple_Pairing_Not_Supported_By_Host",
+        0x38 => "Host_Busy_Pairing",
+        0x39 => "Connection_Rejected_No_Suitable_Channel",
+        0x3A => "Controller_Busy",
+        0x3B => "Unacceptable_Connection_Parameters",
+        0x3C => "Advertising_Timeout",
+        0x3D => "Connection_Terminated_MIC_Failure",
+        0x3E => "Connection_Failed_To_Be_Established",
+        0x40 => "Coarse_Clock_Adjustment_Rejected",
+        0x41 => "Type0_Submap_Not_Defined",
+        0x42 => "Unknown_Advertising_Identifier",
+        0x43 => "Limit_Reached",
+        0x44 => "Operation_Cancelled_By_Host",
+        0x45 => "Packet_Too_Long",
+        _ => return format!("0x{:02x}", code),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 命令：opcode(2) len(1) 参数
+    fn command(opcode: u16, params: &[u8]) -> Vec<u8> {
+        let mut data = opcode.to_le_bytes().to_vec();
+        data.push(params.len() as u8);
+        data.extend_from_slice(params);
+        data
+    }
+
+    const ADDR: [u8; 6] = [0x66, 0x55, 0x44, 0x33, 0x22, 0x11];
+
+    #[test]
+    fn bd_addr_commands() {
+        // Create_Connection_Cancel: BD_ADDR(6)
+        let data = command(0x0408, &ADDR);
+        assert_eq!(
+            summarize(PacketType::Cmd, &data),
+            "Create_Connection_Cancel addr=11:22:33:44:55:66"
+        );
+        assert!(connection_handles(PacketType::Cmd, &data).is_empty());
+
+        // Switch_Role: BD_ADDR(6) role(1)，地址不能当作句柄
+        let mut params = ADDR.to_vec();
+        params.push(0x01);
+        let data = command(0x080B, &params);
+        assert_eq!(
+            summarize(PacketType::Cmd, &data),
+            "Switch_Role addr=11:22:33:44:55:66"
+        );
+        assert!(connection_handles(PacketType::Cmd, &data).is_empty());
+    }
+
+    #[test]
+    fn handle_commands() {
+        // Role_Discovery: handle(2)
+        let data = command(0x0809, &[0x42, 0x10]);
+        assert_eq!(
+            summarize(PacketType::Cmd, &data),
+            "Role_Discovery handle=0x0042"
+        );
+        assert_eq!(connection_handles(PacketType::Cmd, &data), vec![0x0042]);
+
+        // Disconnect: handle(2) reason(1)
+        let data = command(0x0406, &[0x42, 0x00, 0x13]);
+        assert_eq!(connection_handles(PacketType::Cmd, &data), vec![0x0042]);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use serde::Deserialize;
+
+use super::decoder::connection_handles;
+use super::packet::PacketType;
+use super::profile::CompiledProfile;
+
+// 连接的传输类型
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    Le,      // 低功耗
+    Classic, // 经典蓝牙 (BR/EDR)
+}
+
+/// 数据包过滤条件，各条件同时满足的数据包才会写入
+///
+/// 未填写的条件不限制。opcode 和事件码一起构成一组条件，满足其中任意一个即可。
+#[derive(Deserialize, Clone, Default)]
+pub struct PacketFilter {
+    #[serde(default)]
+    handles: Vec<u16>, // 连接句柄
+    #[serde(default)]
+    opcodes: Vec<u16>, // 命令 opcode，对应的 Command Complete/Status 事件一并保留
+    #[serde(default)]
+    events: Vec<u8>, // 事件码
+    #[serde(default)]
+    transport: Option<Transport>, // 只保留 LE 或经典蓝牙
+    #[serde(default)]
+    start_time: Option<String>, // 时间窗口起点，格式与日志中的时间戳相同
+    #[serde(default)]
+    end_time: Option<String>, // 时间窗口终点（含）
+}
+
+/// 过滤状态
+///
+/// 连接句柄属于 LE 还是经典蓝牙要从连接建立的事件中得知，
+/// 因此所有数据包（包括被过滤掉的）都要经过 `matches`。
+pub struct FilterState {
+    handles: HashSet<u16>,
+    opcodes: HashSet<u16>,
+    events: HashSet<u8>,
+    transport: Option<Transport>,
+    start_time: Option<u64>,              // 设备上电后的微秒数
+    end_time: Option<u64>,                // 设备上电后的微秒数
+    transports: HashMap<u16, Transport>,  // 连接句柄对应的传输类型
+    pending_commands: HashMap<u16, bool>, // 等待 Command Complete/Status 的命令及其是否保留
+}
+
+impl FilterState {
+    pub fn new(filter: &PacketFilter, profile: &CompiledProfile) -> io::Result<Self> {
+        let parse_time = |time: &Option<String>| match time.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(time) => profile.parse_uptime(time).map(Some).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid filter time {}: {}", time, e),
+                )
+            }),
+        };
+
+        Ok(Self {
+            handles: filter.handles.iter().map(|h| h & 0x0FFF).collect(),
+            opcodes: filter.opcodes.iter().copied().collect(),
+            events: filter.events.iter().copied().collect(),
+            transport: filter.transport,
+            start_time: parse_time(&filter.start_time)?,
+            end_time: parse_time(&filter.end_time)?,
+            transports: HashMap::new(),
+            pending_commands: HashMap::new(),
+        })
+    }
+
+    /// 设备时间 `uptime`（已接续回绕和重启）是否在时间窗口内，注释记录只按时间过滤
+    pub fn in_time_window(&self, uptime: u64) -> bool {
+        self.start_time.is_none_or(|start| uptime >= start)
+            && self.end_time.is_none_or(|end| uptime <= end)
+    }
+
+    /// 判断数据包是否保留，`data` 不含 H4 头
+    pub fn matches(&mut self, packet_type: PacketType, data: &[u8], uptime: u64) -> bool {
+        self.learn_transport(packet_type, data);
+
+        let handles = connection_handles(packet_type, data);
+        let completed_opcode = completed_opcode(packet_type, data);
+
+        // Command Complete/Status 与对应的命令一起保留或排除，便于查看命令的执行结果
+        if let Some(opcode) = completed_opcode {
+            if let Some(keep) = self.pending_commands.remove(&opcode) {
+                return keep;
+            }
+        }
+
+        let keep = self.in_time_window(uptime)
+            && self.match_handles(&handles)
+            && self.match_codes(packet_type, data, completed_opcode)
+            && self.match_transport(packet_type, data, &handles, completed_opcode);
+
+        if packet_type == PacketType::Cmd {
+            if let Some(opcode) = read_opcode(data) {
+                self.pending_commands.insert(opcode, keep);
+            }
+        }
+        keep
+    }
+
+    fn match_handles(&self, handles: &[u16]) -> bool {
+        self.handles.is_empty() || handles.iter().any(|h| self.handles.contains(h))
+    }
+
+    fn match_codes(
+        &self,
+        packet_type: PacketType,
+        data: &[u8],
+        completed_opcode: Option<u16>,
+    ) -> bool {
+        if self.opcodes.is_empty() && self.events.is_empty() {
+            return true;
+        }
+        match packet_type {
+            PacketType::Cmd => read_opcode(data).is_some_and(|op| self.opcodes.contains(&op)),
+            PacketType::Evt => {
+                data.first().is_some_and(|code| self.events.contains(code))
+                    || completed_opcode.is_some_and(|op| self.opcodes.contains(&op))
+            }
+            // 指定了 opcode 或事件码时不保留数据包
+            PacketType::Acl | PacketType::Sco | PacketType::Iso => false,
+        }
+    }
+
+    fn match_transport(
+        &self,
+        packet_type: PacketType,
+        data: &[u8],
+        handles: &[u16],
+        completed_opcode: Option<u16>,
+    ) -> bool {
+        let Some(transport) = self.transport else {
+            return true;
+        };
+
+        // 无法判断传输类型的数据包（例如 Reset 等通用命令、连接建立前已存在的句柄）保留
+        let packet_transport = match packet_type {
+            PacketType::Sco => Some(Transport::Classic),
+            PacketType::Iso => Some(Transport::Le),
+            _ if !handles.is_empty() => {
+                handles.iter().find_map(|h| self.transports.get(h)).copied()
+            }
+            PacketType::Cmd => read_opcode(data).and_then(opcode_transport),
+            PacketType::Evt => match data.first() {
+                Some(0x3E) => Some(Transport::Le),
+                // 查询、配对等经典蓝牙事件
+                Some(0x01..=0x04 | 0x07 | 0x16..=0x18 | 0x22 | 0x2F | 0x31..=0x36) => {
+                    Some(Transport::Classic)
+                }
+                _ => completed_opcode.and_then(opcode_transport),
+            },
+            PacketType::Acl => None,
+        };
+        packet_transport.is_none_or(|t| t == transport)
+    }
+
+    // 从连接建立的事件中记录句柄的传输类型
+    fn learn_transport(&mut self, packet_type: PacketType, data: &[u8]) {
+        if packet_type != PacketType::Evt {
+            return;
+        }
+        let params = data.get(2..).unwrap_or_default();
+        let transport = match (data.first(), params.first()) {
+            // Connection_Complete / Synchronous_Connection_Complete
+            (Some(0x03 | 0x2C), _) => Transport::Classic,
+            // LE_Connection_Complete / LE_Enhanced_Connection_Complete / LE_CIS_Established
+            (Some(0x3E), Some(0x01 | 0x0A | 0x19)) => Transport::Le,
+            _ => return,
+        };
+        for handle in connection_handles(packet_type, data) {
+            self.transports.insert(handle, transport);
+        }
+    }
+}
+
+// Command Complete/Status 对应的命令 opcode
+fn completed_opcode(packet_type: PacketType, data: &[u8]) -> Option<u16> {
+    if packet_type != PacketType::Evt {
+        return None;
+    }
+    let params = data.get(2..)?;
+    match data.first()? {
+        // Command_Complete: num_packets(1) opcode(2)
+        0x0E => Some(u16::from_le_bytes([*params.get(1)?, *params.get(2)?])),
+        // Command_Status: status(1) num_packets(1) opcode(2)
+        0x0F => Some(u16::from_le_bytes([*params.get(2)?, *params.get(3)?])),
+        _ => None,
+    }
+}
+
+fn read_opcode(data: &[u8]) -> Option<u16> {
+    Some(u16::from_le_bytes([*data.first()?, *data.get(1)?]))
+}
+
+// 根据命令组 (OGF) 判断传输类型，通用命令返回 None
+fn opcode_transport(opcode: u16) -> Option<Transport> {
+    match opcode >> 10 {
+        0x01 | 0x02 => Some(Transport::Classic), // Link Control / Link Policy
+        0x08 => Some(Transport::Le),             // LE Controller
+        _ => None,
+    }
+}
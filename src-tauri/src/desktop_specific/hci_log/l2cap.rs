@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use super::{att, rfcomm, sdp};
+
+// 固定信道
+const CID_SIGNALING: u16 = 0x0001; // BR/EDR 信令
+const CID_ATT: u16 = 0x0004; // LE ATT
+const CID_LE_SIGNALING: u16 = 0x0005; // LE 信令
+const CID_SMP: u16 = 0x0006; // LE 安全管理
+
+// 动态信道的协议
+const PSM_SDP: u16 = 0x0001;
+const PSM_RFCOMM: u16 = 0x0003;
+const PSM_ATT: u16 = 0x001F;
+
+/// 一个完整的 L2CAP 帧（B-frame）
+pub struct L2capFrame {
+    pub handle: u16,      // 连接句柄
+    pub rx: bool,         // 方向，true 为控制器发给主机
+    pub cid: u16,         // 信道 ID
+    pub payload: Vec<u8>, // 信息负载，不含 L2CAP 头
+}
+
+// L2CAP 之上的协议
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Signaling,
+    Att,
+    Smp,
+    Sdp,
+    Rfcomm,
+    Unknown,
+}
+
+/// L2CAP 重组器
+///
+/// ACL 数据包可能把一个 L2CAP 帧拆成多个分片，两个方向的分片会交错出现，
+/// 因此按 (连接句柄, 方向) 分别拼接。同时解析信令信道中的连接请求/响应，
+/// 记录每个动态信道对应的 PSM，用于识别 SDP、RFCOMM 等协议。
+#[derive(Default)]
+pub struct L2capDecoder {
+    partial: HashMap<(u16, bool), Vec<u8>>, // 未完成的帧，含 L2CAP 头
+    channels: HashMap<(u16, bool, u16), u16>, // (连接句柄, 方向, 接收方的 CID) -> PSM
+    pending: HashMap<(u16, bool, u16), u16>, // (连接句柄, 请求方向, 请求方 CID) -> PSM
+}
+
+impl L2capDecoder {
+    /// 加入一个 ACL 数据包（不含 H4 头），凑成完整的 L2CAP 帧时返回该帧
+    ///
+    /// 没有起始分片的后续分片直接丢弃。
+    pub fn push(&mut self, data: &[u8], rx: bool) -> Option<L2capFrame> {
+        let raw_handle = u16::from_le_bytes([*data.first()?, *data.get(1)?]);
+        let handle = raw_handle & 0x0FFF;
+        let payload = data.get(4..)?;
+        let key = (handle, rx);
+
+        // PB 标志：0b00/0b10 为起始分片，0b01 为后续分片
+        if (raw_handle >> 12) & 0x03 == 0x01 {
+            self.partial.get_mut(&key)?.extend_from_slice(payload);
+        } else {
+            self.partial.insert(key, payload.to_vec());
+        }
+
+        let buf = self.partial.get(&key)?;
+        let len = u16::from_le_bytes([*buf.first()?, *buf.get(1)?]) as usize;
+        if buf.len() < 4 + len {
+            return None;
+        }
+        let buf = self.partial.remove(&key)?;
+        let frame = L2capFrame {
+            handle,
+            rx,
+            cid: u16::from_le_bytes([buf[2], buf[3]]),
+            payload: buf[4..4 + len].to_vec(),
+        };
+
+        if frame.cid == CID_SIGNALING {
+            self.learn_channels(&frame);
+        }
+        Some(frame)
+    }
+
+    /// 帧所属的协议
+    pub fn protocol(&self, frame: &L2capFrame) -> Protocol {
+        match frame.cid {
+            CID_SIGNALING | CID_LE_SIGNALING => Protocol::Signaling,
+            CID_ATT => Protocol::Att,
+            CID_SMP => Protocol::Smp,
+            cid => match self.channels.get(&(frame.handle, frame.rx, cid)) {
+                Some(&PSM_SDP) => Protocol::Sdp,
+                Some(&PSM_RFCOMM) => Protocol::Rfcomm,
+                Some(&PSM_ATT) => Protocol::Att,
+                _ => Protocol::Unknown,
+            },
+        }
+    }
+
+    /// 生成帧的可读摘要
+    pub fn summarize(&self, frame: &L2capFrame) -> String {
+        match self.protocol(frame) {
+            Protocol::Signaling => summarize_signaling(&frame.payload),
+            Protocol::Att => att::summarize(&frame.payload),
+            Protocol::Smp => "SMP".to_string(),
+            Protocol::Sdp => sdp::summarize(&frame.payload),
+            Protocol::Rfcomm => rfcomm::summarize(&frame.payload),
+            Protocol::Unknown => {
+                format!("L2CAP cid=0x{:04x} len={}", frame.cid, frame.payload.len())
+            }
+        }
+    }
+
+    // 从 Connection_Request/Response 中记录动态信道的 PSM
+    //
+    // 请求中的 source CID 是请求方的信道，发往请求方的数据使用它；
+    // 响应中的 destination CID 是响应方的信道，发往响应方的数据使用它。
+    fn learn_channels(&mut self, frame: &L2capFrame) {
+        for (code, data) in signaling_commands(&frame.payload) {
+            let read = |offset| read_u16(data, offset);
+            match code {
+                // Connection_Request: psm(2) scid(2)
+                0x02 => {
+                    if let (Some(psm), Some(scid)) = (read(0), read(2)) {
+                        self.channels.insert((frame.handle, !frame.rx, scid), psm);
+                        self.pending.insert((frame.handle, frame.rx, scid), psm);
+                    }
+                }
+                // Connection_Response: dcid(2) scid(2) result(2)
+                0x03 => {
+                    if let (Some(dcid), Some(scid)) = (read(0), read(2)) {
+                        let request = (frame.handle, !frame.rx, scid);
+                        if let Some(&psm) = self.pending.get(&request) {
+                            if dcid != 0 {
+                                self.pending.remove(&request);
+                                self.channels.insert((frame.handle, !frame.rx, dcid), psm);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// 信令帧中可能包含多条命令：code(1) id(1) len(2) data
+fn signaling_commands(payload: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut commands = Vec::new();
+    let mut rest = payload;
+    while rest.len() >= 4 {
+        let len = u16::from_le_bytes([rest[2], rest[3]]) as usize;
+        let end = (4 + len).min(rest.len());
+        commands.push((rest[0], &rest[4..end]));
+        rest = &rest[end..];
+    }
+    commands
+}
+
+fn summarize_signaling(payload: &[u8]) -> String {
+    let commands: Vec<String> = signaling_commands(payload)
+        .into_iter()
+        .map(|(code, data)| {
+            let read = |offset| read_u16(data, offset);
+            let mut summary = format!("L2CAP {}", signaling_name(code));
+            match code {
+                0x02 => {
+                    if let (Some(psm), Some(scid)) = (read(0), read(2)) {
+                        summary.push_str(&format!(" psm=0x{:04x} scid=0x{:04x}", psm, scid));
+                    }
+                }
+                0x03 => {
+                    if let (Some(dcid), Some(scid), Some(result)) = (read(0), read(2), read(4)) {
+                        summary.push_str(&format!(
+                            " dcid=0x{:04x} scid=0x{:04x} result={}",
+                            dcid, scid, result
+                        ));
+                    }
+                }
+                0x06 | 0x07 => {
+                    if let (Some(dcid), Some(scid)) = (read(0), read(2)) {
+                        summary.push_str(&format!(" dcid=0x{:04x} scid=0x{:04x}", dcid, scid));
+                    }
+                }
+                _ => {}
+            }
+            summary
+        })
+        .collect();
+    if commands.is_empty() {
+        "L2CAP signaling (truncated)".to_string()
+    } else {
+        commands.join(", ")
+    }
+}
+
+fn signaling_name(code: u8) -> String {
+    let name = match code {
+        0x01 => "Command_Reject",
+        0x02 => "Connection_Request",
+        0x03 => "Connection_Response",
+        0x04 => "Configuration_Request",
+        0x05 => "Configuration_Response",
+        0x06 => "Disconnection_Request",
+        0x07 => "Disconnection_Response",
+        0x08 => "Echo_Request",
+        0x09 => "Echo_Response",
+        0x0A => "Information_Request",
+        0x0B => "Information_Response",
+        0x12 => "Connection_Parameter_Update_Request",
+        0x13 => "Connection_Parameter_Update_Response",
+        0x14 => "LE_Credit_Based_Connection_Request",
+        0x15 => "LE_Credit_Based_Connection_Response",
+        0x16 => "Flow_Control_Credit_Indication",
+        0x17 => "Credit_Based_Connection_Request",
+        0x18 => "Credit_Based_Connection_Response",
+        _ => return format!("Signaling(0x{:02x})", code),
+    };
+    name.to_string()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
@@ -0,0 +1,245 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use super::btsnoop::{BtsnoopWriter, BTSNOOP_EPOCH_DELTA_US};
+use super::parser::LineParser;
+use super::profile::CompiledProfile;
+use super::report::HciLogReport;
+use super::timeline::{date_base, date_base_from_modified, Timeline};
+use super::writer::{CaptureWriter, PcapngWriter};
+use super::{check_overwrite, compile_profile, HciLogOptions, OutputFormat};
+use crate::utils::AsciiLineReader;
+
+// 串口读超时，决定检查停止标志的间隔
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+// 两次状态事件之间的最小间隔
+const STATUS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize)]
+pub struct HciLiveConfig {
+    port: String,           // 串口设备，例如 /dev/ttyUSB0、COM3
+    baud_rate: u32,         // 波特率
+    output_file: String,    // 输出文件，也可以是命名管道
+    options: HciLogOptions, // 日志格式等选项，与文件转换相同
+}
+
+// 实时抓取状态，通过 "hci-live-status" 事件发送给前端
+#[derive(Serialize, Clone)]
+pub struct HciLiveStatus {
+    running: bool,
+    total_lines: usize,
+    packets: usize,
+    notes: usize,
+    issues: usize,
+    error: Option<String>, // 抓取异常结束的原因，例如串口被拔出
+}
+
+impl HciLiveStatus {
+    fn new(report: &HciLogReport, running: bool, error: Option<String>) -> Self {
+        Self {
+            running,
+            total_lines: report.total_lines,
+            packets: report.packets,
+            notes: report.notes,
+            issues: report.issues.len(),
+            error,
+        }
+    }
+}
+
+// 实时抓取任务的句柄，用于停止任务
+pub struct HciLiveHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<io::Result<HciLogReport>>,
+}
+
+impl HciLiveHandle {
+    /// 抓取线程是否已经结束，例如串口被拔出
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// 停止抓取，等待剩余的数据写入文件后返回统计结果
+    pub fn stop(self) -> io::Result<HciLogReport> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .join()
+            .map_err(|_| io::Error::other("HCI live capture thread panicked"))?
+    }
+}
+
+/// 打开串口，在后台线程中逐行解析并追加写入抓包文件
+///
+/// 每行处理后都会刷新文件，BTSnoop 文件可以在 Wireshark 中随时重新加载；
+/// 输出为 pcapng 格式的命名管道时，可以用 `wireshark -k -i <管道>` 实时查看。
+pub fn start_live_capture(
+    config: HciLiveConfig,
+    app_handle: AppHandle,
+) -> io::Result<HciLiveHandle> {
+    let port = serialport::new(&config.port, config.baud_rate)
+        .timeout(READ_TIMEOUT)
+        .open()?;
+    // 先检查日志格式和输出文件，避免线程启动后才报错
+    let profile = compile_profile(&config.options)?;
+    // 命名管道总是可以写入，普通文件与文件转换一样，已存在时需要允许覆盖
+    if Path::new(&config.output_file).is_file() {
+        check_overwrite(&config.output_file, config.options.overwrite)?;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let reader = StoppableReader {
+        inner: port,
+        stop: stop.clone(),
+    };
+
+    let thread_stop = stop.clone();
+    let thread = thread::spawn(move || {
+        let result = run_live_capture(&config, profile, reader, &thread_stop, &app_handle);
+        let status = match &result {
+            Ok(report) => HciLiveStatus::new(report, false, None),
+            Err(e) => HciLiveStatus::new(&HciLogReport::default(), false, Some(e.to_string())),
+        };
+        if let Err(e) = app_handle.emit("hci-live-status", status) {
+            log::error!("Failed to emit HCI live status: {}", e);
+        }
+        result
+    });
+
+    Ok(HciLiveHandle { stop, thread })
+}
+
+fn run_live_capture<R: Read>(
+    config: &HciLiveConfig,
+    profile: CompiledProfile,
+    reader: R,
+    stop: &AtomicBool,
+    app_handle: &AppHandle,
+) -> io::Result<HciLogReport> {
+    let options = &config.options;
+
+    let Some(file) = open_output(&config.output_file, stop)? else {
+        // 还没有开始读取管道就停止了
+        return Ok(HciLogReport {
+            output_files: vec![config.output_file.clone()],
+            ..Default::default()
+        });
+    };
+    let file = BufWriter::new(file);
+    let capture: Box<dyn CaptureWriter> = match options.output_format {
+        OutputFormat::Btsnoop => Box::new(BtsnoopWriter::new(file)?),
+        OutputFormat::Pcapng => Box::new(PcapngWriter::new(file)?),
+    };
+
+    // 以开始抓取的时刻作为设备上电时刻
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let base_timestamp = now * 1000000 + BTSNOOP_EPOCH_DELTA_US;
+    let date_base = match &options.capture_date {
+        Some(date) => date_base(date, options.utc_offset_minutes)?,
+        None => date_base_from_modified(now, options.utc_offset_minutes),
+    };
+    let timeline = Timeline::new(
+        options.time_base,
+        base_timestamp,
+        date_base,
+        profile.wrap_period(),
+    );
+
+    // 实时抓取只有一个输出，不按设备重启拆分
+    let mut parser = LineParser::new(
+        options,
+        profile,
+        timeline,
+        capture,
+        config.output_file.clone(),
+    )?;
+
+    let mut lines = AsciiLineReader::utf8(BufReader::new(reader));
+    let mut last_status = Instant::now();
+    while let Some(line) = lines.next_line()? {
+        parser.feed(line)?;
+        parser.flush()?;
+
+        if last_status.elapsed() >= STATUS_INTERVAL {
+            last_status = Instant::now();
+            let status = HciLiveStatus::new(parser.report(), true, None);
+            if let Err(e) = app_handle.emit("hci-live-status", status) {
+                log::error!("Failed to emit HCI live status: {}", e);
+            }
+        }
+    }
+
+    parser.finish()
+}
+
+// 打开输出文件，收到停止信号时返回 None
+// 命名管道在 Wireshark 开始读取之前无法打开，以非阻塞方式反复尝试，避免停止时一直等待
+fn open_output(path: &str, stop: &AtomicBool) -> io::Result<Option<File>> {
+    #[cfg(unix)]
+    {
+        use std::fs::OpenOptions;
+        use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+
+        if std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo()) {
+            while !stop.load(Ordering::Relaxed) {
+                match OpenOptions::new()
+                    .write(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(path)
+                {
+                    // 已经有读者，以阻塞方式重新打开不会等待，之后的写入也按普通方式阻塞
+                    // 先保留非阻塞的句柄，避免读者在两次打开之间看到文件结束
+                    Ok(_nonblocking) => return OpenOptions::new().write(true).open(path).map(Some),
+                    // 还没有读者
+                    Err(e) if e.raw_os_error() == Some(libc::ENXIO) => thread::sleep(READ_TIMEOUT),
+                    Err(e) => return Err(e),
+                }
+            }
+            return Ok(None);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = stop;
+
+    File::create(path).map(Some)
+}
+
+// 串口读取：超时后继续等待，收到停止信号时返回文件结束
+struct StoppableReader<R: Read> {
+    inner: R,
+    stop: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for StoppableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                return Ok(0);
+            }
+            match self.inner.read(buf) {
+                // 串口没有文件结束，读到 0 字节也视为暂时没有数据
+                Ok(0) => continue,
+                Err(e) if e.kind() == ErrorKind::TimedOut || e.kind() == ErrorKind::Interrupted => {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 列出可用的串口
+pub fn available_ports() -> io::Result<Vec<String>> {
+    let ports = serialport::available_ports()?;
+    Ok(ports.into_iter().map(|p| p.port_name).collect())
+}
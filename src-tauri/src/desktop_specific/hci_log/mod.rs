@@ -0,0 +1,470 @@
+mod anonymize;
+mod att;
+mod batch;
+pub mod btsnoop;
+mod btsnooz;
+mod decoder;
+mod filter;
+mod l2cap;
+mod live;
+mod packet;
+mod parser;
+mod profile;
+mod reader;
+mod report;
+mod rfcomm;
+mod sdp;
+mod stats;
+mod timeline;
+mod writer;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::state::AppState;
+use crate::utils::{process_ascii_lines_from_file, process_ascii_lines_with_progress};
+use batch::{parse_hci_log_batch_do, HciLogBatchReport};
+use btsnoop::{BtsnoopWriter, BTSNOOP_EPOCH_DELTA_US};
+use btsnooz::{extract_btsnooz_do, BtsnoozReport};
+use filter::PacketFilter;
+pub use live::HciLiveHandle;
+use live::{available_ports, start_live_capture, HciLiveConfig};
+use packet::PacketType;
+use parser::{host_ts_regex, preprocess_line, LineParser};
+use profile::{builtin_profiles, CompiledProfile, LineProfile};
+use reader::{CaptureReader, CaptureRecord};
+use report::HciLogReport;
+use timeline::{date_base, date_base_from_modified, TimeBase, Timeline};
+use writer::{CaptureWriter, PcapngWriter};
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Btsnoop, // BTSnoop (.cfa)
+    Pcapng, // pcapng (.pcapng)，带原始日志行注释
+}
+
+#[derive(Deserialize, Clone)]
+pub struct HciLogOptions {
+    bluetrum_ts: bool, // 蓝讯时间戳
+    skip_chars: u32,   // 跳过字符数
+    #[serde(default)]
+    output_format: OutputFormat, // 输出格式
+    #[serde(default)]
+    keep_notes: bool, // 保留非 HCI 行（MSG、printf 文本）作为注释记录
+    #[serde(default)]
+    decode: bool, // 解码数据包，在结果中返回每个数据包的摘要
+    #[serde(default)]
+    profile: Option<String>, // 内置日志格式名称，默认为 bluetrum
+    #[serde(default)]
+    custom_profile: Option<LineProfile>, // 自定义日志格式，优先于 profile
+    #[serde(default)]
+    time_base: TimeBase, // 记录时间的来源
+    #[serde(default)]
+    capture_date: Option<String>, // 主机时间对应的日期 (YYYY-MM-DD)，默认为文件修改日期
+    #[serde(default)]
+    utc_offset_minutes: i32, // 主机时间的时区偏移（分钟）
+    #[serde(default)]
+    split_sessions: bool, // 设备重启后写入新的文件，每次上电一个文件
+    #[serde(default)]
+    filter: Option<PacketFilter>, // 只保留满足条件的数据包
+    #[serde(default)]
+    gatt_timeline: bool, // 重组 L2CAP 帧，在结果中返回 GATT 操作时间线
+    #[serde(default)]
+    statistics: bool, // 统计吞吐量、命令耗时、错误状态等，在结果中返回统计图表
+    #[serde(default)]
+    output_path: Option<String>, // 输出文件路径，仅转换单个日志时可用，默认为日志同目录下的同名文件
+    #[serde(default)]
+    output_extension: Option<String>, // 未指定输出路径时的扩展名，默认按输出格式为 cfa 或 pcapng
+    #[serde(default)]
+    overwrite: bool, // 覆盖已存在的输出文件，否则报错
+    #[serde(default)]
+    anonymize: bool, // 替换蓝牙地址、清零密钥，并输出地址对照表
+}
+
+// 转换进度，通过 "hci-log-progress" 事件发送给前端
+#[derive(Serialize, Clone)]
+pub struct HciLogProgress {
+    file_path: String,
+    bytes_read: u64,
+    total_bytes: u64,
+}
+
+/// 列出内置的日志格式
+#[tauri::command]
+pub fn hci_log_profiles() -> Vec<LineProfile> {
+    builtin_profiles()
+}
+
+// 大文件转换耗时较长，在后台线程执行，避免阻塞界面
+#[tauri::command(async)]
+pub fn parse_hci_log(
+    app_handle: AppHandle,
+    file_path: &str,
+    options: HciLogOptions,
+    state: State<'_, AppState>,
+) -> Result<HciLogReport, String> {
+    let progress =
+        |bytes_read, total_bytes| emit_progress(&app_handle, file_path, bytes_read, total_bytes);
+    match parse_hci_log_do(file_path, &options, progress) {
+        Ok(report) => {
+            allow_open(&state, &report.output_files);
+            Ok(report)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 批量转换多个日志文件或目录中的日志（.txt/.log），可选合并为一个 pcapng
+#[tauri::command(async)]
+pub fn parse_hci_log_batch(
+    app_handle: AppHandle,
+    paths: Vec<String>,
+    options: HciLogOptions,
+    merge: bool,
+    state: State<'_, AppState>,
+) -> Result<HciLogBatchReport, String> {
+    let progress = |file_path: &str, bytes_read, total_bytes| {
+        emit_progress(&app_handle, file_path, bytes_read, total_bytes)
+    };
+    match parse_hci_log_batch_do(&paths, &options, merge, progress) {
+        Ok(report) => {
+            for file in &report.files {
+                if let Some(file_report) = &file.report {
+                    allow_open(&state, &file_report.output_files);
+                }
+            }
+            allow_open(&state, &report.merged_file);
+            Ok(report)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 列出可用的串口，用于实时抓取
+#[tauri::command]
+pub fn hci_serial_ports() -> Result<Vec<String>, String> {
+    match available_ports() {
+        Ok(ports) => Ok(ports),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 从串口实时抓取 HCI 日志，追加写入抓包文件
+#[tauri::command]
+pub async fn start_hci_live_capture(
+    config: HciLiveConfig,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut handle_guard = state.hci_live_handle.lock().await;
+    if let Some(handle) = handle_guard.as_ref() {
+        if !handle.is_finished() {
+            return Err("HCI live capture is already running".into());
+        }
+        // 上一次抓取已异常结束，错误已通过状态事件报告
+        *handle_guard = None;
+    }
+
+    match start_live_capture(config, app_handle) {
+        Ok(handle) => {
+            *handle_guard = Some(handle);
+            Ok(())
+        }
+        Err(e) => Err(format!("Failed to start HCI live capture: {}", e)),
+    }
+}
+
+/// 停止实时抓取，返回统计结果
+#[tauri::command]
+pub async fn stop_hci_live_capture(state: State<'_, AppState>) -> Result<HciLogReport, String> {
+    let handle = state.hci_live_handle.lock().await.take();
+    let handle = match handle {
+        Some(handle) => handle,
+        None => return Err("No HCI live capture is currently running".into()),
+    };
+
+    // 等待抓取线程写完剩余数据
+    match tokio::task::spawn_blocking(move || handle.stop()).await {
+        Ok(Ok(report)) => {
+            allow_open(&state, &report.output_files);
+            Ok(report)
+        }
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn emit_progress(app_handle: &AppHandle, file_path: &str, bytes_read: u64, total_bytes: u64) {
+    let progress = HciLogProgress {
+        file_path: file_path.to_string(),
+        bytes_read,
+        total_bytes,
+    };
+    if let Err(e) = app_handle.emit("hci-log-progress", progress) {
+        log::error!("Failed to emit HCI log progress: {}", e);
+    }
+}
+
+fn parse_hci_log_do<P: FnMut(u64, u64)>(
+    file_path: &str,
+    options: &HciLogOptions,
+    progress: P,
+) -> io::Result<HciLogReport> {
+    // 日志行格式
+    let profile = compile_profile(options)?;
+
+    // 创建输出文件
+    let (capture, output_file) = create_capture(file_path, options, 1)?;
+
+    // 获取文件元数据
+    let metadata = fs::metadata(file_path)?;
+    let modified_time = metadata.modified()?;
+    let modified_time = modified_time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    // 设备上电时刻，日志中的时间戳为相对它的时间
+    let base_timestamp = modified_time * 1000000 + BTSNOOP_EPOCH_DELTA_US;
+    let date_base = match &options.capture_date {
+        Some(date) => date_base(date, options.utc_offset_minutes)?,
+        None => date_base_from_modified(modified_time, options.utc_offset_minutes),
+    };
+    let mut timeline = Timeline::new(
+        options.time_base,
+        base_timestamp,
+        date_base,
+        profile.wrap_period(),
+    );
+    if options.time_base == TimeBase::HostClock {
+        if let Some((host_clock, uptime)) = find_first_anchor(file_path, options, &profile)? {
+            timeline.set_anchor(host_clock, uptime);
+        }
+    }
+
+    let mut parser = LineParser::new(options, profile, timeline, capture, output_file)?;
+    if options.split_sessions {
+        parser = parser.split_sessions(file_path);
+    }
+
+    // 逐行处理文件内容
+    let mut result = Ok(());
+    process_ascii_lines_with_progress(file_path, progress, |line| {
+        result = parser.feed(line);
+        result.is_ok()
+    })?;
+    result?;
+
+    parser.finish()
+}
+
+fn compile_profile(options: &HciLogOptions) -> io::Result<CompiledProfile> {
+    match &options.custom_profile {
+        Some(custom) => CompiledProfile::new(custom),
+        None => CompiledProfile::builtin(options.profile.as_deref()),
+    }
+}
+
+// 创建输出文件，第一个会话使用 <文件名>.cfa，之后的会话使用 <文件名>_boot<序号>.cfa
+// 返回写入器和输出文件路径
+fn create_capture(
+    file_path: &str,
+    options: &HciLogOptions,
+    session: usize,
+) -> io::Result<(Box<dyn CaptureWriter>, String)> {
+    let path = output_path(file_path, options, session);
+    check_output(file_path, &path, options.overwrite)?;
+    Ok(match options.output_format {
+        OutputFormat::Btsnoop => (Box::new(BtsnoopWriter::create(&path)?), path),
+        OutputFormat::Pcapng => (Box::new(PcapngWriter::create(&path)?), path),
+    })
+}
+
+// 指定了输出路径时使用它（之后的会话在扩展名前加 _boot<序号>），
+// 否则使用日志同目录下的同名文件，扩展名按选项或输出格式决定
+fn output_path(file_path: &str, options: &HciLogOptions, session: usize) -> String {
+    let (base, extension) = match &options.output_path {
+        Some(output_path) => {
+            let (base, extension) = split_extension(output_path);
+            (base.to_string(), extension.to_string())
+        }
+        None => {
+            let extension = match options.output_format {
+                OutputFormat::Btsnoop => "cfa",
+                OutputFormat::Pcapng => "pcapng",
+            };
+            let extension = options
+                .output_extension
+                .as_deref()
+                .map(|ext| ext.trim_start_matches('.'))
+                .filter(|ext| !ext.is_empty())
+                .unwrap_or(extension);
+            (
+                split_extension(file_path).0.to_string(),
+                format!(".{}", extension),
+            )
+        }
+    };
+    if session > 1 {
+        format!("{}_boot{}{}", base, session, extension)
+    } else {
+        format!("{}{}", base, extension)
+    }
+}
+
+// 输出文件已存在时，除非允许覆盖，否则报错；无论如何都不能覆盖正在读取的日志
+fn check_output(file_path: &str, output_file: &str, overwrite: bool) -> io::Result<()> {
+    let output = Path::new(output_file);
+    if !output.exists() {
+        return Ok(());
+    }
+    if fs::canonicalize(output)? == fs::canonicalize(file_path)? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Output file {} is the input log", output_file),
+        ));
+    }
+    check_overwrite(output_file, overwrite)
+}
+
+// 输出文件已存在时，除非允许覆盖，否则报错
+fn check_overwrite(output_file: &str, overwrite: bool) -> io::Result<()> {
+    if !overwrite && Path::new(output_file).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Output file {} already exists", output_file),
+        ));
+    }
+    Ok(())
+}
+
+// 找到第一条同时带主机时间和设备时间的行，作为之前各行插值的锚点
+fn find_first_anchor(
+    file_path: &str,
+    options: &HciLogOptions,
+    profile: &CompiledProfile,
+) -> io::Result<Option<(u64, u64)>> {
+    let host_ts = host_ts_regex();
+    let mut anchor = None;
+    process_ascii_lines_from_file(file_path, |line| {
+        let (_, line, host_clock) = preprocess_line(line, options, &host_ts);
+        if let Some(host_clock) = host_clock {
+            if let Some((ts, _)) = profile.split_timestamp(&line) {
+                if let Ok(uptime) = profile.parse_uptime(ts) {
+                    anchor = Some((host_clock, uptime));
+                    return false;
+                }
+            }
+        }
+        true
+    })?;
+    Ok(anchor)
+}
+
+/// 将 BTSnoop/pcap 抓包文件转换回 "[hh:mm:ss.mmm] CMD => xx xx" 文本格式
+///
+/// 时间为相对第一条记录的时间，输出文件为 `<输入文件>.txt`，返回输出文件路径。
+/// 输出文件已存在时，除非 `options.overwrite`，否则报错。
+#[tauri::command]
+pub fn hci_capture_to_text(file_path: &str, options: HciLogOptions) -> Result<String, String> {
+    match hci_capture_to_text_do(file_path, options.overwrite) {
+        Ok(text_file) => Ok(text_file),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 从安卓 bugreport 中提取 btsnooz 抓包并输出为 BTSnoop 文件
+///
+/// 输出文件的命名和覆盖规则与 `parse_hci_log` 相同。
+#[tauri::command(async)]
+pub fn extract_btsnooz(
+    file_path: &str,
+    options: HciLogOptions,
+    state: State<'_, AppState>,
+) -> Result<BtsnoozReport, String> {
+    match extract_btsnooz_do(file_path, &options) {
+        Ok(report) => {
+            allow_open(&state, &report.output_files);
+            Ok(report)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 用系统中关联的程序（安装 Wireshark 后一般为 Wireshark）打开抓包文件
+///
+/// 只接受本次运行中转换生成的文件，界面不能借此打开任意路径。
+#[tauri::command]
+pub fn open_hci_output(file_path: &str, state: State<'_, AppState>) -> Result<(), String> {
+    let allowed = state.hci_output_files.lock().unwrap().contains(file_path);
+    if !allowed {
+        return Err(format!("{} is not a generated output file", file_path));
+    }
+    tauri_plugin_opener::open_path(file_path, None::<&str>).map_err(|e| e.to_string())
+}
+
+// 记录转换生成的文件，之后可以通过 open_hci_output 打开
+fn allow_open<'a>(state: &AppState, files: impl IntoIterator<Item = &'a String>) {
+    let mut allowed = state.hci_output_files.lock().unwrap();
+    allowed.extend(files.into_iter().cloned());
+}
+
+fn hci_capture_to_text_do(file_path: &str, overwrite: bool) -> io::Result<String> {
+    let mut reader = CaptureReader::open(file_path)?;
+
+    // <输入文件>.txt 可能是用户原来的 UART 日志，与其他输出文件一样按覆盖规则检查
+    let text_file = format!("{}.txt", file_path);
+    check_output(file_path, &text_file, overwrite)?;
+    let mut text = BufWriter::new(File::create(&text_file)?);
+
+    let mut start = None;
+    while let Some(record) = reader.next_record()? {
+        let start = *start.get_or_insert(record.timestamp);
+        if let Some(line) = format_text_line(&record, start) {
+            writeln!(text, "{}", line)?;
+        }
+    }
+    text.flush()?;
+
+    Ok(text_file)
+}
+
+// 按 parse_hci_log 能识别的格式输出一条记录，未知包类型返回 None
+fn format_text_line(record: &CaptureRecord, start: u64) -> Option<String> {
+    let (&h4_type, data) = record.data.split_first()?;
+    let packet_type = PacketType::from_h4_type(h4_type)?;
+    let direction = if record.flags & 0x01 == 0x01 {
+        "<="
+    } else {
+        "=>"
+    };
+
+    let elapsed_ms = record.timestamp.saturating_sub(start) / 1000;
+    let mut line = format!(
+        "[{:02}:{:02}:{:02}.{:03}] {} {}",
+        elapsed_ms / 3600000,
+        elapsed_ms / 60000 % 60,
+        elapsed_ms / 1000 % 60,
+        elapsed_ms % 1000,
+        packet_type.name(),
+        direction
+    );
+    for b in data {
+        line.push_str(&format!(" {:02x}", b));
+    }
+    Some(line)
+}
+
+// 拆分为 (不含扩展名的路径, 含点的扩展名)，Windows 路径中的 '\' 也视为分隔符
+fn split_extension(path: &str) -> (&str, &str) {
+    let name_start = path.rfind(['/', '\\']).map_or(0, |i| i + 1);
+    match path[name_start..].rfind('.') {
+        // 以点开头的文件名（例如 .log）没有扩展名
+        Some(dot_index) if dot_index > 0 => path.split_at(name_start + dot_index),
+        _ => (path, ""),
+    }
+}
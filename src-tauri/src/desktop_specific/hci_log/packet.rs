@@ -0,0 +1,98 @@
+// HCI 包类型
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PacketType {
+    Cmd, // 命令
+    Acl, // ACL 数据
+    Sco, // SCO 语音数据
+    Evt, // 事件
+    Iso, // LE Audio ISO 数据
+}
+
+impl PacketType {
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "CMD" => Some(PacketType::Cmd),
+            "ACL" => Some(PacketType::Acl),
+            "SCO" => Some(PacketType::Sco),
+            "EVT" => Some(PacketType::Evt),
+            "ISO" => Some(PacketType::Iso),
+            _ => None,
+        }
+    }
+
+    pub fn from_h4_type(h4_type: u8) -> Option<Self> {
+        match h4_type {
+            0x01 => Some(PacketType::Cmd),
+            0x02 => Some(PacketType::Acl),
+            0x03 => Some(PacketType::Sco),
+            0x04 => Some(PacketType::Evt),
+            0x05 => Some(PacketType::Iso),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PacketType::Cmd => "CMD",
+            PacketType::Acl => "ACL",
+            PacketType::Sco => "SCO",
+            PacketType::Evt => "EVT",
+            PacketType::Iso => "ISO",
+        }
+    }
+
+    // HCI UART (H4) 包类型头
+    pub fn h4_type(self) -> u8 {
+        match self {
+            PacketType::Cmd => 0x01,
+            PacketType::Acl => 0x02,
+            PacketType::Sco => 0x03,
+            PacketType::Evt => 0x04,
+            PacketType::Iso => 0x05,
+        }
+    }
+
+    // BTSnoop 标志：bit0 为方向（1: 接收），bit1 为命令/事件（1: 命令/事件，0: 数据）
+    // 命令只能发送，事件只能接收，数据包两个方向都可以
+    pub fn btsnoop_flags(self, direction: &str) -> Option<u32> {
+        match (self, direction) {
+            (PacketType::Cmd, "=>") => Some(0x02),
+            (PacketType::Evt, "<=") => Some(0x03),
+            (PacketType::Acl | PacketType::Sco | PacketType::Iso, "=>") => Some(0x00),
+            (PacketType::Acl | PacketType::Sco | PacketType::Iso, "<=") => Some(0x01),
+            _ => None,
+        }
+    }
+
+    /// 根据 HCI 头部的长度字段计算完整数据包的长度（不含 H4 头）
+    ///
+    /// 头部本身还不完整时返回 `None`。
+    pub fn expected_len(self, data: &[u8]) -> Option<usize> {
+        match self {
+            // opcode(2) + 参数长度(1)
+            PacketType::Cmd => data.get(2).map(|&len| 3 + len as usize),
+            // handle(2) + 数据长度(2)
+            PacketType::Acl => {
+                let len = u16::from_le_bytes([*data.get(2)?, *data.get(3)?]);
+                Some(4 + len as usize)
+            }
+            // handle(2) + 数据长度(1)
+            PacketType::Sco => data.get(2).map(|&len| 3 + len as usize),
+            // 事件码(1) + 参数长度(1)
+            PacketType::Evt => data.get(1).map(|&len| 2 + len as usize),
+            // handle(2) + 数据长度(14 bit)
+            PacketType::Iso => {
+                let len = u16::from_le_bytes([*data.get(2)?, *data.get(3)?]) & 0x3FFF;
+                Some(4 + len as usize)
+            }
+        }
+    }
+}
+
+// 将非 HCI 文本封装为厂商自定义事件 (H4 EVT, 事件码 0xFF)，参数为文本内容
+pub fn note_packet(text: &str) -> Vec<u8> {
+    let text = &text.as_bytes()[..text.len().min(u8::MAX as usize)];
+    let mut packet = vec![0x04, 0xFF, text.len() as u8];
+    packet.extend_from_slice(text);
+    packet
+}
@@ -0,0 +1,425 @@
+use std::io;
+
+use regex::Regex;
+
+use super::anonymize::Anonymizer;
+use super::att::{AttRecord, GattTimeline};
+use super::decoder;
+use super::filter::FilterState;
+use super::l2cap::{L2capDecoder, Protocol};
+use super::packet::{note_packet, PacketType};
+use super::profile::{parse_clock, parse_hex, CompiledProfile, ParsedLine};
+use super::report::{HciLogReport, IssueKind, PacketSummary};
+use super::stats::StatsCollector;
+use super::timeline::{TimeBase, Timeline};
+use super::writer::CaptureWriter;
+use super::{create_capture, HciLogOptions};
+
+// 匿名化对照表的后缀，与第一个输出文件放在一起
+pub const MAP_FILE_SUFFIX: &str = ".map.txt";
+
+// 等待续行的数据包
+struct PendingPacket {
+    line_no: usize,          // 起始行号
+    time: String,            // 日志中的时间戳文本
+    uptime: u64,             // 接续回绕和重启后的设备时间，用于按时间窗口过滤
+    timestamp: u64,          // 时间戳
+    packet_type: PacketType, // 包类型
+    flags: u32,              // BTSnoop 标志
+    data: Vec<u8>,           // HCI 数据（不含 H4 头）
+    comment: String,         // 原始日志行，跨多行时以换行连接
+}
+
+impl PendingPacket {
+    // 根据 HCI 头部的长度字段判断数据是否已经完整
+    fn is_complete(&self) -> bool {
+        matches!(self.packet_type.expected_len(&self.data), Some(len) if self.data.len() >= len)
+    }
+}
+
+/// 逐行解析 HCI 日志并写入抓包文件
+///
+/// 文件转换和串口实时抓取共用，调用方负责按行提供日志内容。
+pub struct LineParser {
+    options: HciLogOptions,
+    profile: CompiledProfile,
+    host_ts: Regex,
+    filter: Option<FilterState>,
+    anonymizer: Option<Anonymizer>,
+    l2cap: Option<L2capDecoder>, // 解码数据包或生成 GATT 时间线时重组 L2CAP 帧
+    gatt: Option<GattTimeline>,
+    stats: Option<StatsCollector>,
+    timeline: Timeline,
+    capture: Box<dyn CaptureWriter>,
+    session_file: Option<String>, // 按设备重启拆分输出时，用于生成新文件名的日志路径
+    last_timestamp: u64,          // 最近一条记录的时间戳，没有时间戳的注释行沿用它以保持顺序
+    last_uptime: u64,             // 最近一条记录接续回绕和重启后的设备时间
+    pending: Option<PendingPacket>, // 长度不足、可能在后续行继续的数据包
+    session: usize,
+    report: HciLogReport,
+}
+
+impl LineParser {
+    pub fn new(
+        options: &HciLogOptions,
+        profile: CompiledProfile,
+        timeline: Timeline,
+        capture: Box<dyn CaptureWriter>,
+        output_file: String,
+    ) -> io::Result<Self> {
+        let filter = match &options.filter {
+            Some(filter) => Some(FilterState::new(filter, &profile)?),
+            None => None,
+        };
+        let session = timeline.session();
+        let last_timestamp = timeline.start_timestamp();
+        Ok(Self {
+            options: options.clone(),
+            profile,
+            host_ts: host_ts_regex(),
+            filter,
+            anonymizer: options.anonymize.then(Anonymizer::default),
+            l2cap: (options.decode || options.gatt_timeline).then(L2capDecoder::default),
+            gatt: options.gatt_timeline.then(GattTimeline::default),
+            stats: options.statistics.then(StatsCollector::default),
+            timeline,
+            capture,
+            session_file: None,
+            last_timestamp,
+            last_uptime: 0,
+            pending: None,
+            session,
+            report: HciLogReport {
+                output_files: vec![output_file],
+                ..Default::default()
+            },
+        })
+    }
+
+    /// 设备重启后写入新的文件，文件名由 `file_path` 生成
+    pub fn split_sessions(mut self, file_path: &str) -> Self {
+        self.session_file = Some(file_path.to_string());
+        self
+    }
+
+    /// 当前的统计结果
+    pub fn report(&self) -> &HciLogReport {
+        &self.report
+    }
+
+    /// 处理一行日志
+    pub fn feed(&mut self, line: String) -> io::Result<()> {
+        self.report.total_lines += 1;
+        let line_no = self.report.total_lines;
+
+        let (raw_line, line, host_clock) = preprocess_line(line, &self.options, &self.host_ts);
+
+        // 续行：上一个数据包长度不足时，只包含十六进制字节的行追加到该包
+        if let Some(p) = self.pending.as_mut() {
+            if !p.is_complete() {
+                if let Some(bytes) = parse_continuation_line(&self.profile, &line) {
+                    p.data.extend(bytes);
+                    p.comment.push('\n');
+                    p.comment.push_str(&raw_line);
+                    if p.is_complete() {
+                        self.write_pending()?;
+                    }
+                    return Ok(());
+                }
+            }
+        }
+
+        // 上一个数据包已结束，写入
+        self.write_pending()?;
+
+        let (timestamp_str, packet_type, flags, hci_data) = match self.profile.classify(&line) {
+            ParsedLine::Packet {
+                timestamp,
+                packet_type,
+                flags,
+                data,
+            } => (timestamp, packet_type, flags, data),
+            ParsedLine::Invalid(kind, detail) => {
+                self.report.add_issue(line_no, kind, detail, &raw_line);
+                return Ok(());
+            }
+            ParsedLine::Other => {
+                if !self.options.keep_notes || line.trim().is_empty() {
+                    self.report.ignored_lines += 1;
+                    return Ok(());
+                }
+
+                // 带时间戳的行（例如 "[00:00:00.090] MSG <- 60 01 01"）使用自身的时间戳
+                let (timestamp, text) = match self.profile.split_timestamp(&line) {
+                    Some((ts, rest)) => match self.profile.parse_uptime(ts) {
+                        Ok(uptime) => {
+                            let timestamp = self.timeline.timestamp(uptime, host_clock);
+                            self.last_uptime = self.timeline.uptime();
+                            (timestamp, rest.trim())
+                        }
+                        Err(_) => (self.last_timestamp, line.trim()),
+                    },
+                    None => (self.last_timestamp, line.trim()),
+                };
+                self.last_timestamp = timestamp;
+                self.check_session(line_no)?;
+
+                if let Some(filter) = &self.filter {
+                    if !filter.in_time_window(self.last_uptime) {
+                        self.report.filtered += 1;
+                        return Ok(());
+                    }
+                }
+
+                // 写入注释记录，匿名化时与数据包一样，包注释只保留行号
+                let (text, raw_line) = match self.anonymizer.as_mut() {
+                    Some(anonymizer) => {
+                        (anonymizer.anonymize_text(text), format!("line {}", line_no))
+                    }
+                    None => (text.to_string(), raw_line),
+                };
+                self.capture
+                    .write_packet(timestamp, 0x03, &note_packet(&text), Some(&raw_line))?;
+                self.report.notes += 1;
+                return Ok(());
+            }
+        };
+
+        // 解析时间戳，例如 "[00:00:02.740]"
+        // 设备重启或计数回绕后上电时间从头开始，时间窗口按接续后的时间比较，只匹配一段时间
+        let (uptime, timestamp) = match self.profile.parse_uptime(timestamp_str) {
+            Ok(uptime) => {
+                let timestamp = self.timeline.timestamp(uptime, host_clock);
+                (self.timeline.uptime(), timestamp)
+            }
+            Err(e) => {
+                self.report
+                    .add_issue(line_no, IssueKind::BadTimestamp, e.to_string(), &raw_line);
+                return Ok(());
+            }
+        };
+        self.last_timestamp = timestamp;
+        self.last_uptime = uptime;
+
+        // 上一个数据包已经写入，可以切换文件
+        self.check_session(line_no)?;
+
+        // 长度已经完整的数据包直接写入，否则暂存，等待可能的续行
+        self.pending = Some(PendingPacket {
+            line_no,
+            time: timestamp_str.to_string(),
+            uptime,
+            timestamp,
+            packet_type,
+            flags,
+            data: hci_data,
+            comment: raw_line,
+        });
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(PendingPacket::is_complete)
+        {
+            self.write_pending()?;
+        }
+
+        Ok(())
+    }
+
+    /// 把已写入的内容刷新到文件，实时抓取时供外部读取
+    ///
+    /// 等待续行的数据包不会写入，以免把被拆开的数据包当作完整的包。
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.capture.flush()
+    }
+
+    /// 写入最后一个数据包并刷新文件，返回统计结果
+    pub fn finish(mut self) -> io::Result<HciLogReport> {
+        self.write_pending()?;
+        self.capture.flush()?;
+        self.report.sessions = self.session;
+        if let Some(gatt) = self.gatt.take() {
+            self.report.gatt = gatt.into_transactions();
+        }
+        if let Some(stats) = self.stats.take() {
+            self.report.stats = Some(stats.finish());
+        }
+        if let Some(anonymizer) = self.anonymizer.take() {
+            // 对照表与第一个输出文件放在一起，不要随抓包一起发出
+            let map_file = format!("{}{}", self.report.output_files[0], MAP_FILE_SUFFIX);
+            anonymizer.write_mapping(&map_file)?;
+            self.report.anonymized_addresses = anonymizer.address_count();
+            self.report.address_map = Some(map_file);
+        }
+        Ok(self.report)
+    }
+
+    // 设备重启时开始新的会话，启用拆分时切换到新的输出文件
+    fn check_session(&mut self, line_no: usize) -> io::Result<()> {
+        if self.timeline.session() == self.session {
+            return Ok(());
+        }
+        self.session = self.timeline.session();
+        self.report.reboot_lines.push(line_no);
+
+        if let Some(file_path) = &self.session_file {
+            self.capture.flush()?;
+            let (capture, output_file) = create_capture(file_path, &self.options, self.session)?;
+            self.capture = capture;
+            self.report.output_files.push(output_file);
+        }
+        Ok(())
+    }
+
+    // 写入一个完整的数据包，字节数与 HCI 头部的长度字段不一致时记录到报告并写入注释
+    // 头部本身都不完整的数据包无法解析，直接丢弃
+    fn write_pending(&mut self) -> io::Result<()> {
+        let mut p = match self.pending.take() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let report = &mut self.report;
+
+        let first_line = p.comment.lines().next().unwrap_or_default().to_string();
+        // 原始日志行中的地址和密钥无法可靠替换，匿名化时注释只保留行号
+        let mut comment = match self.anonymizer {
+            Some(_) => format!("line {}", p.line_no),
+            None => p.comment,
+        };
+        match p.packet_type.expected_len(&p.data) {
+            None => {
+                report.add_issue(
+                    p.line_no,
+                    IssueKind::LengthMismatch,
+                    format!(
+                        "incomplete {} header, got {} bytes",
+                        p.packet_type.name(),
+                        p.data.len()
+                    ),
+                    &first_line,
+                );
+                return Ok(());
+            }
+            Some(expected) if expected != p.data.len() => {
+                let detail = format!("expected {}, got {}", expected, p.data.len());
+                comment.push_str(&format!("\n[length mismatch: {}]", detail));
+                report.add_issue(p.line_no, IssueKind::LengthMismatch, detail, &first_line);
+            }
+            _ => {}
+        }
+
+        if let Some(filter) = self.filter.as_mut() {
+            if !filter.matches(p.packet_type, &p.data, p.uptime) {
+                report.filtered += 1;
+                return Ok(());
+            }
+        }
+
+        if let Some(anonymizer) = self.anonymizer.as_mut() {
+            anonymizer.anonymize_packet(p.packet_type, &mut p.data);
+        }
+
+        let mut packet_data = Vec::with_capacity(p.data.len() + 1);
+        packet_data.push(p.packet_type.h4_type()); // 添加 HCI UART 头
+        packet_data.extend_from_slice(&p.data); // 添加 HCI 数据
+
+        self.capture
+            .write_packet(p.timestamp, p.flags, &packet_data, Some(&comment))?;
+        report.count_packet(p.packet_type.name());
+        let frame = report.packets + report.notes;
+        let rx = p.flags & 0x01 == 0x01;
+
+        if let Some(stats) = self.stats.as_mut() {
+            stats.push(p.packet_type, rx, &p.data, p.timestamp);
+        }
+
+        // ACL 分片重组为完整的 L2CAP 帧后再解码上层协议
+        let l2cap_frame = match self.l2cap.as_mut() {
+            Some(l2cap) if p.packet_type == PacketType::Acl => l2cap.push(&p.data, rx),
+            _ => None,
+        };
+
+        if let (Some(l2cap), Some(l2cap_frame), Some(gatt)) =
+            (&self.l2cap, &l2cap_frame, self.gatt.as_mut())
+        {
+            if l2cap.protocol(l2cap_frame) == Protocol::Att {
+                let record = AttRecord {
+                    frame,
+                    line: p.line_no,
+                    time: &p.time,
+                    timestamp: p.timestamp,
+                    connection: l2cap_frame.handle,
+                    rx,
+                };
+                gatt.push(record, &l2cap_frame.payload);
+            }
+        }
+
+        if self.options.decode {
+            let mut summary = decoder::summarize(p.packet_type, &p.data);
+            if let (Some(l2cap), Some(l2cap_frame)) = (&self.l2cap, &l2cap_frame) {
+                summary.push_str(" | ");
+                summary.push_str(&l2cap.summarize(l2cap_frame));
+            }
+            report.summaries.push(PacketSummary {
+                frame,
+                line: p.line_no,
+                time: p.time,
+                packet_type: p.packet_type.name().to_string(),
+                direction: if rx { "<=" } else { "=>" }.to_string(),
+                summary,
+            });
+        }
+        Ok(())
+    }
+}
+
+// 蓝讯时间戳，例如 "(20:44:07.833)"
+pub fn host_ts_regex() -> Regex {
+    Regex::new(r"^\((\d{2}:\d{2}:\d{2}\.\d{3})\)").unwrap()
+}
+
+// 预处理一行：跳过指定字符数，取出并删除蓝讯时间戳
+// 返回 (原始行，作为 pcapng 包注释；处理后的行；主机时间)
+pub fn preprocess_line(
+    line: String,
+    options: &HciLogOptions,
+    host_ts: &Regex,
+) -> (String, String, Option<u64>) {
+    // 跳过指定字符数
+    let line = if options.skip_chars > 0 {
+        line.chars()
+            .skip(options.skip_chars as usize)
+            .collect::<String>()
+    } else {
+        line
+    };
+    // 保留原始行（含蓝讯时间戳）
+    let raw_line = line.trim_end().to_string();
+
+    // 删除蓝讯时间戳（如果启用），使用主机时间时也需要取出
+    if !options.bluetrum_ts && options.time_base != TimeBase::HostClock {
+        return (raw_line, line, None);
+    }
+    match host_ts.captures(&line) {
+        Some(caps) => {
+            let host_clock = parse_clock(&caps[1]).ok();
+            let rest = line[caps.get(0).unwrap().end()..].to_string();
+            (raw_line, rest, host_clock)
+        }
+        None => (raw_line, line, None),
+    }
+}
+
+// 解析续行：只包含十六进制字节，允许带一个行首时间戳
+fn parse_continuation_line(profile: &CompiledProfile, line: &str) -> Option<Vec<u8>> {
+    let bytes = match parse_hex(line) {
+        Ok(bytes) => bytes,
+        Err(_) => parse_hex(profile.split_timestamp(line)?.1).ok()?,
+    };
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(bytes)
+    }
+}
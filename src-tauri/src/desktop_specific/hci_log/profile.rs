@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::io;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::packet::PacketType;
+use super::report::IssueKind;
+
+// 时间戳格式
+#[derive(Deserialize, Serialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    #[default]
+    Clock, // hh:mm:ss.mmm，设备上电时间
+    TicksMs, // 毫秒计数
+    TicksUs, // 微秒计数
+}
+
+/// 日志行格式描述
+///
+/// `packet` 为匹配 HCI 数据包行的正则，命名分组：
+/// - `ts`: 时间戳
+/// - `type`: 包类型，经 `types` 映射后应为 CMD/ACL/SCO/EVT/ISO
+/// - `dir`: 方向（可选），经 `directions` 映射后应为 `=>`/`<=`，
+///   缺省时命令按发送、事件按接收处理
+/// - `data`: 十六进制数据，字节之间可以有空格也可以没有
+///
+/// `ts_prefix` 用于从普通文本行中取出行首时间戳，需包含 `ts` 分组。
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct LineProfile {
+    pub name: String,
+    pub packet: String,
+    #[serde(default)]
+    pub ts_prefix: Option<String>,
+    #[serde(default)]
+    pub timestamp: TimestampFormat,
+    #[serde(default)]
+    pub types: HashMap<String, String>,
+    #[serde(default)]
+    pub directions: HashMap<String, String>,
+}
+
+/// 内置的日志格式，第一个为默认格式
+pub fn builtin_profiles() -> Vec<LineProfile> {
+    vec![
+        // [00:00:02.740] CMD => 1b 04 02 ff ff
+        LineProfile {
+            name: "bluetrum".to_string(),
+            packet: r"^\s*(?P<ts>\[[^\]]*\])\s+(?P<type>\S+)\s+(?P<dir>=>|<=)(?P<data>.*)$"
+                .to_string(),
+            ts_prefix: Some(r"^\s*(?P<ts>\[[^\]]*\])".to_string()),
+            timestamp: TimestampFormat::Clock,
+            types: HashMap::new(),
+            directions: HashMap::new(),
+        },
+        // 123456 HCI_CMD: 030c00
+        // 123460 HCI_ACL <rx>: 0200100c00...
+        LineProfile {
+            name: "hci_tick_ms".to_string(),
+            packet: r"^\s*\[?(?P<ts>\d+)\]?\s+HCI_(?P<type>CMD|ACL|SCO|EVT|ISO)\s*(?P<dir><tx>|<rx>)?\s*:\s*(?P<data>.*)$"
+                .to_string(),
+            ts_prefix: Some(r"^\s*\[?(?P<ts>\d+)\]?\s".to_string()),
+            timestamp: TimestampFormat::TicksMs,
+            types: HashMap::new(),
+            directions: HashMap::from([
+                ("<tx>".to_string(), "=>".to_string()),
+                ("<rx>".to_string(), "<=".to_string()),
+            ]),
+        },
+    ]
+}
+
+// 一行日志的解析结果
+pub enum ParsedLine<'a> {
+    // HCI 数据包行
+    Packet {
+        timestamp: &'a str,
+        packet_type: PacketType,
+        flags: u32,
+        data: Vec<u8>,
+    },
+    // 形如 HCI 数据包行但存在问题
+    Invalid(IssueKind, String),
+    // 普通文本行（MSG、printf 文本等）
+    Other,
+}
+
+/// 编译后的日志行格式
+pub struct CompiledProfile {
+    packet: Regex,
+    ts_prefix: Option<Regex>,
+    timestamp: TimestampFormat,
+    types: HashMap<String, String>,
+    directions: HashMap<String, String>,
+}
+
+impl CompiledProfile {
+    pub fn new(profile: &LineProfile) -> io::Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid pattern in profile {}: {}", profile.name, e),
+                )
+            })
+        };
+
+        let packet = compile(&profile.packet)?;
+        for group in ["ts", "type", "data"] {
+            if !packet.capture_names().any(|name| name == Some(group)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Profile {} is missing group {}", profile.name, group),
+                ));
+            }
+        }
+
+        Ok(Self {
+            packet,
+            ts_prefix: profile.ts_prefix.as_deref().map(compile).transpose()?,
+            timestamp: profile.timestamp,
+            types: profile.types.clone(),
+            directions: profile.directions.clone(),
+        })
+    }
+
+    /// 按名称查找内置格式，名称为空时使用默认格式
+    pub fn builtin(name: Option<&str>) -> io::Result<Self> {
+        let profiles = builtin_profiles();
+        let profile = match name {
+            None => &profiles[0],
+            Some(name) => profiles.iter().find(|p| p.name == name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown profile {}", name),
+                )
+            })?,
+        };
+        Self::new(profile)
+    }
+
+    /// 解析一行日志
+    pub fn classify<'a>(&self, line: &'a str) -> ParsedLine<'a> {
+        let caps = match self.packet.captures(line) {
+            Some(caps) => caps,
+            None => return ParsedLine::Other,
+        };
+        let timestamp = caps.name("ts").map_or("", |m| m.as_str());
+        let type_token = caps.name("type").map_or("", |m| m.as_str());
+        let data = caps.name("data").map_or("", |m| m.as_str());
+
+        // 检查包类型
+        let mapped_type = self
+            .types
+            .get(type_token)
+            .map_or(type_token, |t| t.as_str());
+        let packet_type = match PacketType::from_token(mapped_type) {
+            Some(t) => t,
+            None => {
+                return ParsedLine::Invalid(
+                    IssueKind::UnknownType,
+                    format!("unknown packet type {}", type_token),
+                )
+            }
+        };
+
+        // 检查方向，缺省时根据包类型推断
+        let direction = match caps.name("dir") {
+            Some(m) => self
+                .directions
+                .get(m.as_str())
+                .map_or(m.as_str(), |d| d.as_str()),
+            None => match packet_type {
+                PacketType::Cmd => "=>",
+                PacketType::Evt => "<=",
+                _ => "",
+            },
+        };
+        let flags = match packet_type.btsnoop_flags(direction) {
+            Some(flags) => flags,
+            None if direction.is_empty() => {
+                return ParsedLine::Invalid(
+                    IssueKind::BadDirection,
+                    format!("{} without direction", type_token),
+                )
+            }
+            None => {
+                return ParsedLine::Invalid(
+                    IssueKind::BadDirection,
+                    format!("{} cannot be {}", type_token, direction),
+                )
+            }
+        };
+
+        // 检查数据部分是否为有效的十六进制
+        let data = match parse_hex(data) {
+            Ok(data) => data,
+            Err(token) => {
+                return ParsedLine::Invalid(
+                    IssueKind::NonHexByte,
+                    format!("invalid byte {}", token),
+                )
+            }
+        };
+
+        ParsedLine::Packet {
+            timestamp,
+            packet_type,
+            flags,
+            data,
+        }
+    }
+
+    /// 拆分行首时间戳，返回时间戳和剩余内容
+    pub fn split_timestamp<'a>(&self, line: &'a str) -> Option<(&'a str, &'a str)> {
+        let caps = self.ts_prefix.as_ref()?.captures(line)?;
+        let ts = caps.name("ts")?.as_str();
+        let rest = &line[caps.get(0)?.end()..];
+        Some((ts, rest))
+    }
+
+    /// 时间戳的回绕周期（微秒），`hh:mm:ss.mmm` 每 24 小时回绕一次
+    pub fn wrap_period(&self) -> Option<u64> {
+        match self.timestamp {
+            TimestampFormat::Clock => Some(24 * 3600 * 1000000),
+            TimestampFormat::TicksMs | TimestampFormat::TicksUs => None,
+        }
+    }
+
+    /// 解析时间戳，返回设备上电后的微秒数
+    pub fn parse_uptime(&self, timestamp_str: &str) -> io::Result<u64> {
+        // 去掉时间戳的方括号
+        let timestamp_str = timestamp_str.trim_start_matches('[').trim_end_matches(']');
+
+        match self.timestamp {
+            TimestampFormat::Clock => parse_clock(timestamp_str),
+            TimestampFormat::TicksMs => parse_ticks(timestamp_str).map(|t| t * 1000),
+            TimestampFormat::TicksUs => parse_ticks(timestamp_str),
+        }
+    }
+}
+
+/// 解析十六进制数据，字节之间可以有空格也可以没有，失败时返回出错的片段
+pub fn parse_hex(data: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for token in data.split_whitespace() {
+        if token.len() <= 2 {
+            bytes.push(u8::from_str_radix(token, 16).map_err(|_| token.to_string())?);
+            continue;
+        }
+
+        // 连续的十六进制字符串，按两个字符一组解析
+        if token.len() % 2 != 0 || !token.is_ascii() {
+            return Err(token.to_string());
+        }
+        for i in (0..token.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(&token[i..i + 2], 16).map_err(|_| token.to_string())?);
+        }
+    }
+    Ok(bytes)
+}
+
+fn invalid_timestamp(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// 解析 hh:mm:ss.mmm 格式的时间，返回微秒数
+pub fn parse_clock(timestamp_str: &str) -> io::Result<u64> {
+    // 分割为秒和小数部分
+    let parts: Vec<&str> = timestamp_str.split('.').collect();
+    if parts.len() != 2 {
+        return Err(invalid_timestamp("Invalid timestamp format"));
+    }
+
+    // 解析秒部分
+    let time_parts: Vec<&str> = parts[0].split(':').collect();
+    if time_parts.len() != 3 {
+        return Err(invalid_timestamp("Invalid timestamp format"));
+    }
+
+    let hours: u64 = time_parts[0]
+        .parse()
+        .map_err(|_| invalid_timestamp("Failed to parse hours"))?;
+    let minutes: u64 = time_parts[1]
+        .parse()
+        .map_err(|_| invalid_timestamp("Failed to parse minutes"))?;
+    let seconds: u64 = time_parts[2]
+        .parse()
+        .map_err(|_| invalid_timestamp("Failed to parse seconds"))?;
+
+    // 解析小数部分，按位数换算为微秒（.740 为毫秒，.740123 为微秒）
+    let fraction = parts[1];
+    if fraction.is_empty() || fraction.len() > 6 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid_timestamp("Failed to parse microseconds"));
+    }
+    let microseconds: u64 = fraction.parse::<u64>().unwrap() * 10u64.pow(6 - fraction.len() as u32);
+
+    Ok((hours * 3600 + minutes * 60 + seconds) * 1000000 + microseconds)
+}
+
+fn parse_ticks(timestamp_str: &str) -> io::Result<u64> {
+    timestamp_str
+        .trim()
+        .parse()
+        .map_err(|_| invalid_timestamp("Failed to parse tick counter"))
+}
@@ -0,0 +1,368 @@
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read};
+
+use super::btsnoop::{
+    flags_from_h4, BtsnoopHeader, BtsnoopRecord, Datalink, BTSNOOP_EPOCH_DELTA_US, MAX_RECORD_LEN,
+};
+
+// pcap 链路类型
+const LINKTYPE_H4: u32 = 187; // LINKTYPE_BLUETOOTH_HCI_H4
+const LINKTYPE_H4_WITH_PHDR: u32 = 201; // LINKTYPE_BLUETOOTH_HCI_H4_WITH_PHDR
+
+// pcap 记录的最大长度：4 字节方向伪头 + 最长的 H4 数据包
+const MAX_PCAP_RECORD_LEN: usize = 4 + MAX_RECORD_LEN;
+// 需要读入内存解析的 pcapng 块（SHB、IDB、EPB）的最大长度，EPB 中除数据外还可能有注释等选项
+const MAX_PCAPNG_BLOCK_LEN: usize = 0x40000;
+
+// pcapng 块类型
+const BLOCK_SHB: u32 = 0x0A0D0D0A; // Section Header Block
+const BLOCK_IDB: u32 = 0x00000001; // Interface Description Block
+const BLOCK_EPB: u32 = 0x00000006; // Enhanced Packet Block
+
+/// 从抓包文件读出的一条记录
+///
+/// 与 `CaptureWriter` 的参数约定一致：`timestamp` 为 BTSnoop 时间基准，
+/// `flags` 为 BTSnoop 标志位，`data` 带 H4 类型头。
+#[derive(Debug, Clone)]
+pub struct CaptureRecord {
+    pub timestamp: u64,
+    pub flags: u32,
+    pub data: Vec<u8>,
+    pub comment: Option<String>, // pcapng 包注释
+}
+
+// pcapng 接口信息
+struct Interface {
+    linktype: u32,
+    units_per_sec: u64, // 时间戳精度，默认为微秒
+}
+
+// 支持的输入格式
+enum Format {
+    Btsnoop {
+        datalink: Datalink,
+    },
+    Pcap {
+        linktype: u32,
+        big_endian: bool,
+        nanosecond: bool,
+    },
+    Pcapng {
+        big_endian: bool,
+    },
+}
+
+/// 抓包文件读取器，支持 BTSnoop（数据链路类型 1001/1002）以及 H4 链路类型的 pcap 和 pcapng
+pub struct CaptureReader<R: Read> {
+    inner: R,
+    format: Format,
+    interfaces: Vec<Interface>, // pcapng 当前段的接口
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: &str) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+
+        let format = if &magic == b"btsnoop\0" {
+            let mut header = [0u8; 16];
+            header[..8].copy_from_slice(&magic);
+            inner.read_exact(&mut header[8..])?;
+            let header = BtsnoopHeader::parse(&header)?;
+            Format::Btsnoop {
+                datalink: header.datalink,
+            }
+        } else if magic[..4] == [0x0A, 0x0D, 0x0D, 0x0A] {
+            // Section Header Block：块类型、块长度、字节序标记
+            let mut byte_order = [0u8; 4];
+            inner.read_exact(&mut byte_order)?;
+            let big_endian = match byte_order {
+                [0x1A, 0x2B, 0x3C, 0x4D] => true,
+                [0x4D, 0x3C, 0x2B, 0x1A] => false,
+                _ => return Err(invalid_data("Invalid pcapng byte order".to_string())),
+            };
+            // 跳过 SHB 剩余部分
+            let block_len = read_u32(&magic[4..8], big_endian) as usize;
+            if block_len < 12 {
+                return Err(invalid_data("Invalid pcapng block length".to_string()));
+            }
+            skip(&mut inner, block_len - 12)?;
+            Format::Pcapng { big_endian }
+        } else {
+            let (big_endian, nanosecond) = match [magic[0], magic[1], magic[2], magic[3]] {
+                [0xD4, 0xC3, 0xB2, 0xA1] => (false, false),
+                [0xA1, 0xB2, 0xC3, 0xD4] => (true, false),
+                [0x4D, 0x3C, 0xB2, 0xA1] => (false, true),
+                [0xA1, 0xB2, 0x3C, 0x4D] => (true, true),
+                _ => return Err(invalid_data("Unknown capture file format".to_string())),
+            };
+
+            // 剩余的 pcap 全局头：时区、精度、snaplen、链路类型
+            let mut header = [0u8; 16];
+            inner.read_exact(&mut header)?;
+            let linktype = read_u32(&header[12..16], big_endian);
+            if linktype != LINKTYPE_H4 && linktype != LINKTYPE_H4_WITH_PHDR {
+                return Err(invalid_data(format!(
+                    "Unsupported pcap link type {}",
+                    linktype
+                )));
+            }
+            Format::Pcap {
+                linktype,
+                big_endian,
+                nanosecond,
+            }
+        };
+
+        Ok(Self {
+            inner,
+            format,
+            interfaces: Vec::new(),
+        })
+    }
+
+    /// 读取下一条记录，文件结束时返回 `None`
+    pub fn next_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        match self.format {
+            Format::Btsnoop { datalink } => {
+                let Some(record) = BtsnoopRecord::read(&mut self.inner)? else {
+                    return Ok(None);
+                };
+                Ok(Some(CaptureRecord {
+                    timestamp: record.timestamp,
+                    flags: record.flags,
+                    data: record.to_h4(datalink),
+                    comment: None,
+                }))
+            }
+            Format::Pcap {
+                linktype,
+                big_endian,
+                nanosecond,
+            } => {
+                let mut header = [0u8; 16];
+                if !read_header(&mut self.inner, &mut header)? {
+                    return Ok(None);
+                }
+                let seconds = read_u32(&header[0..4], big_endian) as u64;
+                let fraction = read_u32(&header[4..8], big_endian) as u64;
+                let included_len = read_u32(&header[8..12], big_endian) as usize;
+                if included_len > MAX_PCAP_RECORD_LEN {
+                    return Err(invalid_data(format!(
+                        "pcap record is too long: {} bytes",
+                        included_len
+                    )));
+                }
+
+                let mut data = vec![0u8; included_len];
+                self.inner.read_exact(&mut data)?;
+
+                let micros = if nanosecond {
+                    fraction / 1000
+                } else {
+                    fraction
+                };
+                let timestamp = seconds * 1000000 + micros + BTSNOOP_EPOCH_DELTA_US;
+
+                h4_record(linktype, timestamp, data, None).map(Some)
+            }
+            Format::Pcapng { big_endian } => self.next_pcapng_record(big_endian),
+        }
+    }
+
+    // 读取下一个 Enhanced Packet Block，跳过其他类型的块
+    fn next_pcapng_record(&mut self, mut big_endian: bool) -> io::Result<Option<CaptureRecord>> {
+        loop {
+            let mut header = [0u8; 8];
+            if !read_header(&mut self.inner, &mut header)? {
+                return Ok(None);
+            }
+            let block_type = read_u32(&header[0..4], big_endian);
+            let block_len = read_u32(&header[4..8], big_endian) as usize;
+            if block_len < 12 {
+                return Err(invalid_data("Invalid pcapng block length".to_string()));
+            }
+            // 其他块（统计信息、名称解析等）直接跳过，不读入内存
+            if ![BLOCK_SHB, BLOCK_IDB, BLOCK_EPB].contains(&block_type) {
+                skip(&mut self.inner, block_len - 8)?;
+                continue;
+            }
+            if block_len > MAX_PCAPNG_BLOCK_LEN {
+                return Err(invalid_data(format!(
+                    "pcapng block is too long: {} bytes",
+                    block_len
+                )));
+            }
+            // 块内容和结尾的块长度
+            let mut body = vec![0u8; block_len - 8];
+            self.inner.read_exact(&mut body)?;
+            body.truncate(block_len - 12);
+
+            match block_type {
+                // 新的段，字节序可能改变，接口重新编号
+                BLOCK_SHB => {
+                    big_endian = match body.get(..4) {
+                        Some([0x1A, 0x2B, 0x3C, 0x4D]) => true,
+                        Some([0x4D, 0x3C, 0x2B, 0x1A]) => false,
+                        _ => return Err(invalid_data("Invalid pcapng byte order".to_string())),
+                    };
+                    self.format = Format::Pcapng { big_endian };
+                    self.interfaces.clear();
+                }
+                BLOCK_IDB => {
+                    if body.len() < 8 {
+                        return Err(invalid_data("Truncated pcapng interface".to_string()));
+                    }
+                    let linktype = read_u16(&body[0..2], big_endian) as u32;
+                    let mut units_per_sec = 1000000;
+                    for (code, value) in pcapng_options(&body[8..], big_endian) {
+                        // if_tsresol：最高位为 0 表示 10 的负幂，为 1 表示 2 的负幂
+                        if code == 9 && !value.is_empty() {
+                            let exp = (value[0] & 0x7F) as u32;
+                            units_per_sec = if value[0] & 0x80 == 0 {
+                                10u64.checked_pow(exp)
+                            } else {
+                                2u64.checked_pow(exp)
+                            }
+                            .ok_or_else(|| invalid_data("Invalid pcapng if_tsresol".to_string()))?;
+                        }
+                    }
+                    self.interfaces.push(Interface {
+                        linktype,
+                        units_per_sec,
+                    });
+                }
+                BLOCK_EPB => {
+                    if body.len() < 20 {
+                        return Err(invalid_data("Truncated pcapng packet".to_string()));
+                    }
+                    let interface_id = read_u32(&body[0..4], big_endian) as usize;
+                    let ts_high = read_u32(&body[4..8], big_endian) as u64;
+                    let ts_low = read_u32(&body[8..12], big_endian) as u64;
+                    let captured_len = read_u32(&body[12..16], big_endian) as usize;
+                    let data_end = 20 + captured_len;
+                    if body.len() < data_end {
+                        return Err(invalid_data("Truncated pcapng packet".to_string()));
+                    }
+                    let interface = self.interfaces.get(interface_id).ok_or_else(|| {
+                        invalid_data(format!("Unknown pcapng interface {}", interface_id))
+                    })?;
+                    if interface.linktype != LINKTYPE_H4
+                        && interface.linktype != LINKTYPE_H4_WITH_PHDR
+                    {
+                        return Err(invalid_data(format!(
+                            "Unsupported pcapng link type {}",
+                            interface.linktype
+                        )));
+                    }
+
+                    let units = ts_high << 32 | ts_low;
+                    let micros = (units as u128 * 1000000 / interface.units_per_sec as u128) as u64;
+                    let timestamp = micros + BTSNOOP_EPOCH_DELTA_US;
+
+                    // opt_comment
+                    let options_start = data_end.div_ceil(4) * 4;
+                    let comment =
+                        pcapng_options(body.get(options_start..).unwrap_or(&[]), big_endian)
+                            .find(|(code, _)| *code == 1)
+                            .map(|(_, value)| String::from_utf8_lossy(value).into_owned());
+
+                    let data = body[20..data_end].to_vec();
+                    return h4_record(interface.linktype, timestamp, data, comment).map(Some);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// 把 H4 链路类型的数据转换为记录
+// 201 带 4 字节大端方向伪头，187 只能根据包类型推断方向
+fn h4_record(
+    linktype: u32,
+    timestamp: u64,
+    mut data: Vec<u8>,
+    comment: Option<String>,
+) -> io::Result<CaptureRecord> {
+    let received = if linktype == LINKTYPE_H4_WITH_PHDR {
+        if data.len() < 4 {
+            return Err(invalid_data("Truncated pcap record".to_string()));
+        }
+        let direction = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        data.drain(..4);
+        direction & 0x01 == 0x01
+    } else {
+        data.first() == Some(&0x04)
+    };
+
+    let flags = flags_from_h4(data.first().copied().unwrap_or(0), received);
+
+    Ok(CaptureRecord {
+        timestamp,
+        flags,
+        data,
+        comment,
+    })
+}
+
+// 遍历 pcapng 选项，返回 (选项代码, 值)，遇到 opt_endofopt 或数据不完整时结束
+fn pcapng_options(mut buf: &[u8], big_endian: bool) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < 4 {
+            return None;
+        }
+        let code = read_u16(&buf[0..2], big_endian);
+        let len = read_u16(&buf[2..4], big_endian) as usize;
+        if code == 0 || buf.len() < 4 + len {
+            return None;
+        }
+        let value = &buf[4..4 + len];
+        buf = &buf[(4 + len.div_ceil(4) * 4).min(buf.len())..];
+        Some((code, value))
+    })
+}
+
+fn skip<R: Read>(inner: &mut R, len: usize) -> io::Result<()> {
+    let skipped = io::copy(&mut inner.take(len as u64), &mut io::sink())?;
+    if skipped < len as u64 {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+// 读取记录头，文件正好结束时返回 false
+fn read_header<R: Read>(inner: &mut R, header: &mut [u8]) -> io::Result<bool> {
+    match inner.read_exact(header) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+    let bytes = [bytes[0], bytes[1]];
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
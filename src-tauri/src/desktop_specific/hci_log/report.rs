@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::att::GattTransaction;
+use super::stats::HciLogStats;
+
+// 问题行的原因
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    BadTimestamp,   // 时间戳无法解析
+    UnknownType,    // 未知的包类型
+    BadDirection,   // 包类型与方向不匹配，例如 CMD <=
+    LengthMismatch, // 字节数与 HCI 头部的长度字段不一致
+    NonHexByte,     // 数据部分包含非十六进制字节
+}
+
+// 一条被跳过或存在问题的行
+#[derive(Serialize, Debug, Clone)]
+pub struct LineIssue {
+    pub line: usize,     // 行号，从 1 开始
+    pub kind: IssueKind, // 原因
+    pub detail: String,  // 详细说明
+    pub text: String,    // 原始行内容
+}
+
+// 一个数据包的解码摘要
+#[derive(Serialize, Debug, Clone)]
+pub struct PacketSummary {
+    pub frame: usize,        // 帧序号，从 1 开始，与 Wireshark 中一致
+    pub line: usize,         // 行号
+    pub time: String,        // 日志中的时间戳
+    pub packet_type: String, // 包类型
+    pub direction: String,   // 方向 (=>, <=)
+    pub summary: String,     // 摘要
+}
+
+/// HCI 日志转换结果
+///
+/// 有问题的 HCI 行都会记录在 `issues` 中，普通文本行只计数。
+#[derive(Serialize, Debug, Default)]
+pub struct HciLogReport {
+    pub total_lines: usize,              // 总行数
+    pub packets: usize,                  // 写入的数据包数
+    pub notes: usize,                    // 写入的注释记录数
+    pub ignored_lines: usize,            // 忽略的非 HCI 行数
+    pub filtered: usize,                 // 被过滤条件排除的数据包和注释记录数
+    pub counts: BTreeMap<String, usize>, // 每种包类型的数量
+    pub issues: Vec<LineIssue>,          // 问题行
+    pub summaries: Vec<PacketSummary>,   // 数据包摘要，仅在启用解码时返回
+    pub gatt: Vec<GattTransaction>,      // GATT 操作时间线，仅在启用时返回
+    pub stats: Option<HciLogStats>,      // 统计结果，仅在启用时返回
+    pub sessions: usize,                 // 设备上电会话数，日志中途重启时大于 1
+    pub reboot_lines: Vec<usize>,        // 检测到设备重启的行号
+    pub output_files: Vec<String>,       // 输出的抓包文件，按设备重启拆分时有多个
+    pub address_map: Option<String>,     // 匿名化时输出的地址对照表
+    pub anonymized_addresses: usize,     // 匿名化的地址数
+}
+
+impl HciLogReport {
+    pub fn add_issue(&mut self, line: usize, kind: IssueKind, detail: String, text: &str) {
+        self.issues.push(LineIssue {
+            line,
+            kind,
+            detail,
+            text: text.to_string(),
+        });
+    }
+
+    pub fn count_packet(&mut self, name: &str) {
+        self.packets += 1;
+        *self.counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,62 @@
+/// 生成一个 RFCOMM 帧的可读摘要
+///
+/// 只解析帧头：DLCI、帧类型和长度，DLCI 0 上的 UIH 帧再给出多路复用命令类型。
+pub fn summarize(frame: &[u8]) -> String {
+    let (Some(&address), Some(&control)) = (frame.first(), frame.get(1)) else {
+        return "RFCOMM (truncated)".to_string();
+    };
+    let dlci = address >> 2;
+
+    // 长度字段：EA 位为 1 时占 1 字节，否则占 2 字节
+    let (len, header_len) = match frame.get(2) {
+        Some(&l) if l & 0x01 == 0x01 => ((l >> 1) as usize, 3),
+        Some(&l) => match frame.get(3) {
+            Some(&h) => (((h as usize) << 7) | (l >> 1) as usize, 4),
+            None => return "RFCOMM (truncated)".to_string(),
+        },
+        None => return "RFCOMM (truncated)".to_string(),
+    };
+
+    // 去掉 P/F 位后的帧类型
+    let frame_type = match control & !0x10 {
+        0x2F => "SABM",
+        0x63 => "UA",
+        0x0F => "DM",
+        0x43 => "DISC",
+        0xEF => "UIH",
+        _ => return format!("RFCOMM control=0x{:02x} dlci={}", control, dlci),
+    };
+
+    let mut summary = format!("RFCOMM {} dlci={}", frame_type, dlci);
+    if frame_type == "UIH" {
+        if dlci == 0 {
+            if let Some(&mux_type) = frame.get(header_len) {
+                summary.push_str(&format!(" {}", mux_command_name(mux_type >> 2)));
+            }
+        } else {
+            // 带 P/F 位的 UIH 帧在数据前有 1 字节的信用
+            if control & 0x10 == 0x10 {
+                if let Some(&credits) = frame.get(header_len) {
+                    summary.push_str(&format!(" credits={}", credits));
+                }
+            }
+            summary.push_str(&format!(" len={}", len));
+        }
+    }
+    summary
+}
+
+fn mux_command_name(command: u8) -> String {
+    let name = match command {
+        0x20 => "PN",
+        0x08 => "Test",
+        0x28 => "FCon",
+        0x18 => "FCoff",
+        0x38 => "MSC",
+        0x04 => "NSC",
+        0x24 => "RPN",
+        0x14 => "RLS",
+        _ => return format!("mux(0x{:02x})", command),
+    };
+    name.to_string()
+}
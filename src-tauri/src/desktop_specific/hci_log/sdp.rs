@@ -0,0 +1,30 @@
+/// 生成一个 SDP PDU 的可读摘要：pdu_id(1) transaction_id(2) parameter_length(2)
+pub fn summarize(pdu: &[u8]) -> String {
+    let (Some(&pdu_id), Some(&hi), Some(&lo)) = (pdu.first(), pdu.get(1), pdu.get(2)) else {
+        return "SDP (truncated)".to_string();
+    };
+    let transaction_id = u16::from_be_bytes([hi, lo]);
+
+    let mut summary = format!("SDP {} tid=0x{:04x}", pdu_name(pdu_id), transaction_id);
+    // Error_Response: error_code(2)
+    if pdu_id == 0x01 {
+        if let (Some(&hi), Some(&lo)) = (pdu.get(5), pdu.get(6)) {
+            summary.push_str(&format!(" error=0x{:04x}", u16::from_be_bytes([hi, lo])));
+        }
+    }
+    summary
+}
+
+fn pdu_name(pdu_id: u8) -> String {
+    let name = match pdu_id {
+        0x01 => "Error_Response",
+        0x02 => "Service_Search_Request",
+        0x03 => "Service_Search_Response",
+        0x04 => "Service_Attribute_Request",
+        0x05 => "Service_Attribute_Response",
+        0x06 => "Service_Search_Attribute_Request",
+        0x07 => "Service_Search_Attribute_Response",
+        _ => return format!("PDU(0x{:02x})", pdu_id),
+    };
+    name.to_string()
+}
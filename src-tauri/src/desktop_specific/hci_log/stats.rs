@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, HashMap};
+
+use plotly::common::{Mode, Title};
+use plotly::layout::{Axis, GridPattern, LayoutGrid, RowOrder};
+use plotly::{Bar, BoxPlot, Layout, Plot, Scatter};
+use serde::Serialize;
+
+use super::decoder::{
+    event_name, le_subevent_name, opcode_name, status_name, STATUS_HANDLE_EVENTS,
+};
+use super::packet::PacketType;
+
+// 吞吐量统计窗口
+const THROUGHPUT_WINDOW_US: u64 = 1000000;
+// 发出 ACL 数据后超过该时间没有收到 Number_Of_Completed_Packets 视为流控停顿
+const STALL_THRESHOLD_US: u64 = 200000;
+
+// 一个连接的 ACL 收发统计
+#[derive(Serialize, Debug, Clone)]
+pub struct AclThroughput {
+    pub handle: u16,       // 连接句柄
+    pub tx_bytes: usize,   // 发送的 ACL 数据字节数（不含 ACL 头）
+    pub rx_bytes: usize,   // 接收的 ACL 数据字节数（不含 ACL 头）
+    pub peak_tx_kbps: f64, // 统计窗口内的最高发送速率
+    pub peak_rx_kbps: f64, // 统计窗口内的最高接收速率
+}
+
+// 一种命令的执行耗时
+#[derive(Serialize, Debug, Clone)]
+pub struct CommandLatency {
+    pub opcode: u16,
+    pub name: String,
+    pub count: usize, // 收到 Command Complete/Status 的次数
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+// 一次流控停顿
+#[derive(Serialize, Debug, Clone)]
+pub struct FlowStall {
+    pub handle: u16,        // 连接句柄
+    pub time_s: f64,        // 开始等待的时间，相对第一个数据包
+    pub duration_ms: f64,   // 等待 Number_Of_Completed_Packets 的时间
+    pub outstanding: usize, // 等待期间未完成的数据包数
+}
+
+/// HCI 日志统计结果
+#[derive(Serialize, Debug, Default)]
+pub struct HciLogStats {
+    pub acl: Vec<AclThroughput>,                     // 每个连接的吞吐量
+    pub commands: Vec<CommandLatency>,               // 每种命令的耗时
+    pub statuses: BTreeMap<String, usize>,           // 非零状态码出现的次数
+    pub disconnect_reasons: BTreeMap<String, usize>, // 断开原因出现的次数
+    pub stalls: Vec<FlowStall>,                      // 流控停顿
+    pub plot: String,                                // plotly 图表 HTML
+}
+
+// 一个连接的流控状态
+#[derive(Default)]
+struct FlowState {
+    outstanding: usize, // 已发送、未收到完成通知的数据包数
+    wait_start: u64,    // 开始等待完成通知的时间戳
+}
+
+/// 统计收集器，按顺序传入写入的每个数据包
+#[derive(Default)]
+pub struct StatsCollector {
+    start: Option<u64>,                                      // 第一个数据包的时间戳
+    throughput: BTreeMap<(u16, bool), BTreeMap<u64, usize>>, // (句柄, 方向) -> 窗口序号 -> 字节数
+    pending_commands: HashMap<u16, u64>,                     // opcode -> 发送时间戳
+    latencies: BTreeMap<u16, Vec<f64>>,                      // opcode -> 耗时 (ms)
+    statuses: BTreeMap<String, usize>,
+    disconnect_reasons: BTreeMap<String, usize>,
+    flows: HashMap<u16, FlowState>,
+    stalls: Vec<FlowStall>,
+}
+
+impl StatsCollector {
+    /// 加入一个数据包，`data` 不含 H4 头，`timestamp` 为微秒
+    pub fn push(&mut self, packet_type: PacketType, rx: bool, data: &[u8], timestamp: u64) {
+        let start = *self.start.get_or_insert(timestamp);
+        let elapsed = timestamp.saturating_sub(start);
+
+        match packet_type {
+            PacketType::Acl => {
+                let Some(handle) = read_u16(data, 0).map(|h| h & 0x0FFF) else {
+                    return;
+                };
+                let bytes = data.len().saturating_sub(4);
+                *self
+                    .throughput
+                    .entry((handle, rx))
+                    .or_default()
+                    .entry(elapsed / THROUGHPUT_WINDOW_US)
+                    .or_insert(0) += bytes;
+
+                if !rx {
+                    let flow = self.flows.entry(handle).or_default();
+                    if flow.outstanding == 0 {
+                        flow.wait_start = timestamp;
+                    }
+                    flow.outstanding += 1;
+                }
+            }
+            PacketType::Cmd => {
+                if let Some(opcode) = read_u16(data, 0) {
+                    self.pending_commands.insert(opcode, timestamp);
+                }
+            }
+            PacketType::Evt => self.push_event(data, timestamp, start),
+            PacketType::Sco | PacketType::Iso => {}
+        }
+    }
+
+    fn push_event(&mut self, data: &[u8], timestamp: u64, start: u64) {
+        let Some(&code) = data.first() else {
+            return;
+        };
+        let params = data.get(2..).unwrap_or_default();
+
+        // (状态码, 状态码所属的命令或事件)
+        let status = match code {
+            // Command_Complete: num_packets(1) opcode(2) status(1)
+            0x0E => {
+                let opcode = read_u16(params, 1);
+                self.complete_command(opcode, timestamp);
+                params.get(3).zip(opcode.map(opcode_name))
+            }
+            // Command_Status: status(1) num_packets(1) opcode(2)
+            0x0F => {
+                let opcode = read_u16(params, 2);
+                self.complete_command(opcode, timestamp);
+                params.first().zip(opcode.map(opcode_name))
+            }
+            // Disconnection_Complete: status(1) handle(2) reason(1)
+            0x05 => {
+                if let Some(&reason) = params.get(3) {
+                    *self
+                        .disconnect_reasons
+                        .entry(status_name(reason))
+                        .or_insert(0) += 1;
+                }
+                // 断开后控制器丢弃未发送的数据，不再有完成通知
+                if let Some(handle) = read_u16(params, 1) {
+                    self.flows.remove(&(handle & 0x0FFF));
+                }
+                params.first().map(|s| (s, event_name(code)))
+            }
+            // Number_Of_Completed_Packets: num_handles(1) [handle(2) count(2)]...
+            0x13 => {
+                let num_handles = params.first().copied().unwrap_or(0) as usize;
+                for i in 0..num_handles {
+                    if let (Some(handle), Some(count)) =
+                        (read_u16(params, 1 + i * 4), read_u16(params, 3 + i * 4))
+                    {
+                        self.complete_packets(handle & 0x0FFF, count as usize, timestamp, start);
+                    }
+                }
+                None
+            }
+            // 参数以状态开头的事件
+            0x03 | 0x07 => params.first().map(|s| (s, event_name(code))),
+            _ if STATUS_HANDLE_EVENTS.contains(&code) => {
+                params.first().map(|s| (s, event_name(code)))
+            }
+            // LE Meta：连接完成等子事件的参数以状态开头
+            0x3E => match params.first() {
+                Some(&sub @ (0x01 | 0x03 | 0x04 | 0x0A | 0x0C | 0x19)) => {
+                    params.get(1).map(|s| (s, le_subevent_name(sub)))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some((&status, source)) = status {
+            if status != 0 {
+                let key = format!("{} ({})", status_name(status), source);
+                *self.statuses.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+
+    fn complete_command(&mut self, opcode: Option<u16>, timestamp: u64) {
+        let Some(opcode) = opcode else {
+            return;
+        };
+        // opcode 0 为控制器空闲时的流控通知，不对应任何命令
+        if let Some(sent) = self.pending_commands.remove(&opcode) {
+            let latency = timestamp.saturating_sub(sent) as f64 / 1000.0;
+            self.latencies.entry(opcode).or_default().push(latency);
+        }
+    }
+
+    fn complete_packets(&mut self, handle: u16, count: usize, timestamp: u64, start: u64) {
+        let Some(flow) = self.flows.get_mut(&handle) else {
+            return;
+        };
+        let waited = timestamp.saturating_sub(flow.wait_start);
+        if flow.outstanding > 0 && waited > STALL_THRESHOLD_US {
+            self.stalls.push(FlowStall {
+                handle,
+                time_s: flow.wait_start.saturating_sub(start) as f64 / 1e6,
+                duration_ms: waited as f64 / 1000.0,
+                outstanding: flow.outstanding,
+            });
+        }
+        flow.outstanding = flow.outstanding.saturating_sub(count);
+        flow.wait_start = timestamp;
+    }
+
+    /// 汇总统计结果并生成图表
+    pub fn finish(self) -> HciLogStats {
+        let mut acl: BTreeMap<u16, AclThroughput> = BTreeMap::new();
+        for (&(handle, rx), windows) in &self.throughput {
+            let entry = acl.entry(handle).or_insert(AclThroughput {
+                handle,
+                tx_bytes: 0,
+                rx_bytes: 0,
+                peak_tx_kbps: 0.0,
+                peak_rx_kbps: 0.0,
+            });
+            let total: usize = windows.values().sum();
+            let peak = windows.values().copied().map(kbps).fold(0.0, f64::max);
+            if rx {
+                entry.rx_bytes = total;
+                entry.peak_rx_kbps = peak;
+            } else {
+                entry.tx_bytes = total;
+                entry.peak_tx_kbps = peak;
+            }
+        }
+
+        let commands = self
+            .latencies
+            .iter()
+            .map(|(&opcode, samples)| CommandLatency {
+                opcode,
+                name: opcode_name(opcode),
+                count: samples.len(),
+                min_ms: samples.iter().copied().fold(f64::INFINITY, f64::min),
+                avg_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+                max_ms: samples.iter().copied().fold(0.0, f64::max),
+            })
+            .collect();
+
+        let plot = self.plot();
+        HciLogStats {
+            acl: acl.into_values().collect(),
+            commands,
+            statuses: self.statuses,
+            disconnect_reasons: self.disconnect_reasons,
+            stalls: self.stalls,
+            plot,
+        }
+    }
+
+    // 上到下依次为：ACL 吞吐量、命令耗时、非零状态码、断开原因、流控停顿
+    fn plot(&self) -> String {
+        let mut plot = Plot::new();
+
+        for (&(handle, rx), windows) in &self.throughput {
+            // 补齐没有数据的窗口，折线才会回到 0
+            let last = windows.keys().next_back().copied().unwrap_or(0);
+            let times: Vec<f64> = (0..=last)
+                .map(|i| (i * THROUGHPUT_WINDOW_US) as f64 / 1e6)
+                .collect();
+            let rates: Vec<f64> = (0..=last)
+                .map(|i| kbps(windows.get(&i).copied().unwrap_or(0)))
+                .collect();
+            let name = format!("0x{:04x} {}", handle, if rx { "RX" } else { "TX" });
+            plot.add_trace(Scatter::new(times, rates).name(name).mode(Mode::Lines));
+        }
+
+        for (&opcode, samples) in &self.latencies {
+            plot.add_trace(
+                BoxPlot::new(samples.clone())
+                    .name(opcode_name(opcode))
+                    .x_axis("x2")
+                    .y_axis("y2"),
+            );
+        }
+
+        plot.add_trace(
+            Bar::new(
+                self.statuses.keys().cloned().collect(),
+                self.statuses.values().copied().collect(),
+            )
+            .name("Status")
+            .x_axis("x3")
+            .y_axis("y3"),
+        );
+        plot.add_trace(
+            Bar::new(
+                self.disconnect_reasons.keys().cloned().collect(),
+                self.disconnect_reasons.values().copied().collect(),
+            )
+            .name("Disconnect reason")
+            .x_axis("x4")
+            .y_axis("y4"),
+        );
+
+        let mut stalls: BTreeMap<u16, Vec<&FlowStall>> = BTreeMap::new();
+        for stall in &self.stalls {
+            stalls.entry(stall.handle).or_default().push(stall);
+        }
+        for (handle, stalls) in stalls {
+            plot.add_trace(
+                Scatter::new(
+                    stalls.iter().map(|s| s.time_s).collect(),
+                    stalls.iter().map(|s| s.duration_ms).collect(),
+                )
+                .name(format!("0x{:04x} stall", handle))
+                .mode(Mode::Markers)
+                .x_axis("x5")
+                .y_axis("y5"),
+            );
+        }
+
+        let axis = |title: &str| Axis::new().title(Title::with_text(title));
+        let layout = Layout::new()
+            .grid(
+                LayoutGrid::new()
+                    .rows(5)
+                    .columns(1)
+                    .pattern(GridPattern::Independent)
+                    .row_order(RowOrder::TopToBottom),
+            )
+            .height(1600)
+            .x_axis(axis("时间 (s)"))
+            .y_axis(axis("ACL 吞吐量 (kbps)"))
+            .y_axis2(axis("命令耗时 (ms)"))
+            .y_axis3(axis("非零状态码次数"))
+            .y_axis4(axis("断开原因次数"))
+            .x_axis5(axis("时间 (s)"))
+            .y_axis5(axis("流控停顿 (ms)"));
+        plot.set_layout(layout);
+
+        plot.to_inline_html(None)
+    }
+}
+
+// 一个统计窗口内的字节数换算为 kbps
+fn kbps(bytes: usize) -> f64 {
+    bytes as f64 * 8.0 / 1000.0 / (THROUGHPUT_WINDOW_US as f64 / 1e6)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
@@ -0,0 +1,191 @@
+use std::io;
+
+use serde::Deserialize;
+
+use super::btsnoop::BTSNOOP_EPOCH_DELTA_US;
+
+const DAY_US: u64 = 24 * 3600 * 1000000;
+
+// 小于该值的时间倒退视为日志行乱序，不认为是重启
+const JITTER_US: u64 = 1000000;
+// 计数回绕时，回绕前后的时间与回绕周期的最大距离
+const WRAP_MARGIN_US: u64 = 10 * 60 * 1000000;
+
+// 记录时间的来源
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeBase {
+    #[default]
+    Uptime, // 文件修改时间 + 设备上电时间
+    HostClock, // 蓝讯时间戳 (hh:mm:ss.mmm) 中的主机时间 + 日期
+}
+
+/// 把日志中的时间换算为 BTSnoop 时间戳
+///
+/// - `Uptime`：文件修改时间作为设备上电时刻，加上设备上电时间。
+/// - `HostClock`：有主机时间的行直接使用主机时间（跨零点自动进位到下一天），
+///   没有主机时间的行以最近一次主机时间为锚点，按设备上电时间的差值插值。
+///
+/// 设备上电时间倒退时，接近回绕周期的视为计数回绕（例如 `[hh:mm:ss]` 超过 24 小时），
+/// 其余视为设备重启，开始新的会话。两种情况下都把之后的上电时间接续到之前的时间上，
+/// 保证输出的时间戳单调递增。
+pub struct Timeline {
+    time_base: TimeBase,
+    uptime_base: u64,             // 设备上电时刻
+    date_base: u64,               // 主机时间所在日期的零点（已换算为 UTC）
+    last_host_clock: Option<u64>, // 上一次的主机时间（当天的微秒数），用于判断跨天
+    anchor: Option<(u64, u64)>,   // (时间戳, 设备上电时间)，插值锚点
+    wrap_period: Option<u64>,     // 设备上电时间的回绕周期
+    uptime_offset: u64,           // 回绕和重启累计的上电时间补偿
+    last_uptime: Option<u64>,     // 上一次日志中的上电时间
+    last_timestamp: u64,          // 上一次输出的时间戳
+    session: usize,               // 当前会话序号，从 1 开始，每次重启加 1
+}
+
+impl Timeline {
+    pub fn new(
+        time_base: TimeBase,
+        uptime_base: u64,
+        date_base: u64,
+        wrap_period: Option<u64>,
+    ) -> Self {
+        Self {
+            time_base,
+            uptime_base,
+            date_base,
+            last_host_clock: None,
+            anchor: None,
+            wrap_period,
+            uptime_offset: 0,
+            last_uptime: None,
+            last_timestamp: 0,
+            session: 1,
+        }
+    }
+
+    /// 设备上电时刻，没有时间戳的记录以它为起点
+    pub fn start_timestamp(&self) -> u64 {
+        self.uptime_base
+    }
+
+    /// 当前会话序号，从 1 开始
+    pub fn session(&self) -> usize {
+        self.session
+    }
+
+    /// 最近一条记录的上电时间，已接续回绕和重启，整个日志中单调递增
+    pub fn uptime(&self) -> u64 {
+        self.uptime_offset + self.last_uptime.unwrap_or(0)
+    }
+
+    /// 设置初始锚点，用于第一条主机时间之前的行
+    pub fn set_anchor(&mut self, host_clock: u64, uptime: u64) {
+        self.anchor = Some((self.date_base + host_clock, uptime));
+    }
+
+    /// `uptime` 为设备上电时间，`host_clock` 为当天的主机时间，单位均为微秒
+    pub fn timestamp(&mut self, uptime: u64, host_clock: Option<u64>) -> u64 {
+        let uptime = self.continuous_uptime(uptime);
+        let timestamp = self.convert(uptime, host_clock).max(self.last_timestamp);
+        self.last_timestamp = timestamp;
+        timestamp
+    }
+
+    // 处理回绕和重启，返回连续的上电时间
+    fn continuous_uptime(&mut self, uptime: u64) -> u64 {
+        if let Some(last) = self.last_uptime {
+            if uptime + JITTER_US < last {
+                match self.wrap_period {
+                    Some(period) if last + WRAP_MARGIN_US >= period && uptime < WRAP_MARGIN_US => {
+                        self.uptime_offset += period;
+                    }
+                    _ => {
+                        self.uptime_offset += last;
+                        self.session += 1;
+                    }
+                }
+            } else if uptime < last {
+                // 轻微乱序，沿用上一次的时间
+                return self.uptime_offset + last;
+            }
+        }
+        self.last_uptime = Some(uptime);
+        self.uptime_offset + uptime
+    }
+
+    fn convert(&mut self, uptime: u64, host_clock: Option<u64>) -> u64 {
+        if self.time_base == TimeBase::Uptime {
+            return self.uptime_base + uptime;
+        }
+
+        if let Some(host_clock) = host_clock {
+            // 主机时间倒退超过 12 小时，认为跨过了零点
+            if let Some(last) = self.last_host_clock {
+                if host_clock + DAY_US / 2 < last {
+                    self.date_base += DAY_US;
+                }
+            }
+            self.last_host_clock = Some(host_clock);
+
+            let timestamp = self.date_base + host_clock;
+            self.anchor = Some((timestamp, uptime));
+            return timestamp;
+        }
+
+        match self.anchor {
+            Some((timestamp, anchor_uptime)) => {
+                if uptime >= anchor_uptime {
+                    timestamp + (uptime - anchor_uptime)
+                } else {
+                    timestamp.saturating_sub(anchor_uptime - uptime)
+                }
+            }
+            None => self.uptime_base + uptime,
+        }
+    }
+}
+
+/// 计算日期零点的 BTSnoop 时间戳
+///
+/// `date` 格式为 YYYY-MM-DD，`utc_offset_minutes` 为本地时区相对 UTC 的偏移。
+pub fn date_base(date: &str, utc_offset_minutes: i32) -> io::Result<u64> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid capture date {}, expected YYYY-MM-DD", date),
+        )
+    };
+
+    let parts: Vec<&str> = date.trim().split('-').collect();
+    if parts.len() != 3 {
+        return Err(invalid());
+    }
+    let year: i64 = parts[0].parse().map_err(|_| invalid())?;
+    let month: i64 = parts[1].parse().map_err(|_| invalid())?;
+    let day: i64 = parts[2].parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86400 - utc_offset_minutes as i64 * 60;
+    Ok((seconds.max(0) as u64) * 1000000 + BTSNOOP_EPOCH_DELTA_US)
+}
+
+/// 未指定日期时，取文件修改时间所在的本地日期
+pub fn date_base_from_modified(modified: u64, utc_offset_minutes: i32) -> u64 {
+    let offset = utc_offset_minutes as i64 * 60;
+    let local_day = (modified as i64 + offset).div_euclid(86400);
+    let seconds = local_day * 86400 - offset;
+    (seconds.max(0) as u64) * 1000000 + BTSNOOP_EPOCH_DELTA_US
+}
+
+// 公历日期到 1970-01-01 的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
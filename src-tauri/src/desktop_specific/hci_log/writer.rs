@@ -0,0 +1,152 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use super::btsnoop::BTSNOOP_EPOCH_DELTA_US;
+
+// LINKTYPE_BLUETOOTH_HCI_H4_WITH_PHDR
+const LINKTYPE_H4_WITH_PHDR: u16 = 201;
+
+/// 抓包文件写入接口
+///
+/// `timestamp` 统一使用 BTSnoop 时间基准（公元 0 年起的微秒数），
+/// `flags` 使用 BTSnoop 的标志位约定（bit0: 方向，bit1: 命令/事件），
+/// `data` 为带 H4 类型头的 HCI 数据包。
+pub trait CaptureWriter {
+    fn write_packet(
+        &mut self,
+        timestamp: u64,
+        flags: u32,
+        data: &[u8],
+        comment: Option<&str>,
+    ) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// pcapng 格式写入器（LINKTYPE_BLUETOOTH_HCI_H4_WITH_PHDR）
+///
+/// 每个数据包前带 4 字节大端方向伪头（0: 发送，1: 接收），
+/// 并可以附带一条包注释（opt_comment）。
+/// 合并多个日志时每个来源使用一个接口，`CaptureWriter::write_packet` 写入接口 0。
+pub struct PcapngWriter<W: Write> {
+    inner: W,
+}
+
+impl PcapngWriter<BufWriter<File>> {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn create_with_interfaces(path: &str, names: &[String]) -> io::Result<Self> {
+        Self::with_interfaces(BufWriter::new(File::create(path)?), names)
+    }
+}
+
+impl<W: Write> PcapngWriter<W> {
+    pub fn new(inner: W) -> io::Result<Self> {
+        Self::with_interfaces(inner, &[])
+    }
+
+    /// 每个名称对应一个接口（if_name），名称为空时只创建一个未命名的接口
+    pub fn with_interfaces(mut inner: W, names: &[String]) -> io::Result<Self> {
+        // Section Header Block
+        let mut shb = Vec::new();
+        shb.extend_from_slice(&0x1A2B3C4D_u32.to_le_bytes()); // 字节序标记
+        shb.extend_from_slice(&1_u16.to_le_bytes()); // 主版本号
+        shb.extend_from_slice(&0_u16.to_le_bytes()); // 次版本号
+        shb.extend_from_slice(&(-1_i64).to_le_bytes()); // 段长度未知
+        write_block(&mut inner, 0x0A0D0D0A, &shb)?;
+
+        // Interface Description Block，时间精度使用默认的微秒
+        let names: Vec<Option<&str>> = match names {
+            [] => vec![None],
+            _ => names.iter().map(|name| Some(name.as_str())).collect(),
+        };
+        for name in names {
+            let mut idb = Vec::new();
+            idb.extend_from_slice(&LINKTYPE_H4_WITH_PHDR.to_le_bytes());
+            idb.extend_from_slice(&0_u16.to_le_bytes()); // 保留
+            idb.extend_from_slice(&0_u32.to_le_bytes()); // 不限制抓包长度
+            if let Some(name) = name {
+                write_option(&mut idb, 2, name.as_bytes()); // if_name
+                write_option(&mut idb, 0, &[]); // opt_endofopt
+            }
+            write_block(&mut inner, 0x00000001, &idb)?;
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// 写入指定接口的数据包，参数约定与 `CaptureWriter::write_packet` 相同
+    pub fn write_packet_on(
+        &mut self,
+        interface_id: u32,
+        timestamp: u64,
+        flags: u32,
+        data: &[u8],
+        comment: Option<&str>,
+    ) -> io::Result<()> {
+        let ts = timestamp.saturating_sub(BTSNOOP_EPOCH_DELTA_US);
+
+        // 方向伪头 + H4 数据包
+        let mut packet = Vec::with_capacity(data.len() + 4);
+        packet.extend_from_slice(&(flags & 0x01).to_be_bytes());
+        packet.extend_from_slice(data);
+
+        // Enhanced Packet Block
+        let mut epb = Vec::with_capacity(packet.len() + 32);
+        epb.extend_from_slice(&interface_id.to_le_bytes()); // 接口 ID
+        epb.extend_from_slice(&((ts >> 32) as u32).to_le_bytes()); // 时间戳高 32 位
+        epb.extend_from_slice(&(ts as u32).to_le_bytes()); // 时间戳低 32 位
+        epb.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // 抓取长度
+        epb.extend_from_slice(&(packet.len() as u32).to_le_bytes()); // 原始长度
+        epb.extend_from_slice(&packet);
+        pad_to_u32(&mut epb);
+
+        if let Some(comment) = comment {
+            write_option(&mut epb, 1, comment.as_bytes()); // opt_comment
+            write_option(&mut epb, 0, &[]); // opt_endofopt
+        }
+
+        write_block(&mut self.inner, 0x00000006, &epb)
+    }
+}
+
+impl<W: Write> CaptureWriter for PcapngWriter<W> {
+    fn write_packet(
+        &mut self,
+        timestamp: u64,
+        flags: u32,
+        data: &[u8],
+        comment: Option<&str>,
+    ) -> io::Result<()> {
+        self.write_packet_on(0, timestamp, flags, data, comment)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// 写入一个 pcapng 块，body 需已按 4 字节对齐
+fn write_block<W: Write>(w: &mut W, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let total_len = (body.len() + 12) as u32;
+    w.write_all(&block_type.to_le_bytes())?;
+    w.write_all(&total_len.to_le_bytes())?;
+    w.write_all(body)?;
+    w.write_all(&total_len.to_le_bytes())
+}
+
+fn write_option(buf: &mut Vec<u8>, code: u16, value: &[u8]) {
+    // 选项长度字段为 u16，超长注释直接截断
+    let value = &value[..value.len().min(u16::MAX as usize - 3)];
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    buf.extend_from_slice(value);
+    pad_to_u32(buf);
+}
+
+fn pad_to_u32(buf: &mut Vec<u8>) {
+    let padded = buf.len().div_ceil(4) * 4;
+    buf.resize(padded, 0);
+}
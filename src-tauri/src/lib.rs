@@ -16,9 +16,12 @@ use desktop_specific::*;
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 use analyze_thread::{analyze_thread_plot, analyze_thread_preprocess, generate_plot};
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
-use exception_log::process_exception_log;
+use exception_log::{exception_log_formats, process_exception_log};
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
-use hci_log::parse_hci_log;
+use hci_log::{
+    extract_btsnooz, hci_capture_to_text, hci_log_profiles, hci_serial_ports, open_hci_output,
+    parse_hci_log, parse_hci_log_batch, start_hci_live_capture, stop_hci_live_capture,
+};
 // #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 // use audio_converter::convert_audio;
 
@@ -69,10 +72,19 @@ pub fn run() {
             .invoke_handler(tauri::generate_handler![
                 get_platform,
                 process_exception_log,
+                exception_log_formats,
                 generate_plot,
                 analyze_thread_preprocess,
                 analyze_thread_plot,
                 parse_hci_log,
+                parse_hci_log_batch,
+                hci_capture_to_text,
+                extract_btsnooz,
+                hci_log_profiles,
+                hci_serial_ports,
+                start_hci_live_capture,
+                stop_hci_live_capture,
+                open_hci_output,
                 nettool_start_test,
                 nettool_stop_test,
                 commands::start_speed_test,
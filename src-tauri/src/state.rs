@@ -1,17 +1,30 @@
 
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use crate::hci_log::HciLiveHandle;
 use crate::speed_test::SpeedTestHandle;
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 // 应用的全局状态
 pub struct AppState {
     pub speed_test_handle: Arc<Mutex<Option<SpeedTestHandle>>>,
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    pub hci_live_handle: Arc<Mutex<Option<HciLiveHandle>>>,
+    // HCI 日志转换生成的文件，界面只能打开这些文件
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    pub hci_output_files: std::sync::Mutex<HashSet<String>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
             speed_test_handle: Arc::new(Mutex::new(None)),
+            #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+            hci_live_handle: Arc::new(Mutex::new(None)),
+            #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+            hci_output_files: std::sync::Mutex::new(HashSet::new()),
         }
     }
 }
@@ -1,7 +1,7 @@
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufRead, BufReader, Read};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum FileEncoding {
     UTF8,
     UTF16LE,
@@ -11,21 +11,175 @@ pub enum FileEncoding {
     Unknown,
 }
 
-pub fn detect_encoding(file_path: &str) -> io::Result<FileEncoding> {
-    let mut file = File::open(file_path)?;
-    let mut buffer = [0u8; 4];
-    file.read_exact(&mut buffer)?;
-
-    match buffer {
-        [0xEF, 0xBB, 0xBF, _] => Ok(FileEncoding::UTF8),
-        [0xFF, 0xFE, 0x00, 0x00] => Ok(FileEncoding::UTF32LE),
-        [0x00, 0x00, 0xFE, 0xFF] => Ok(FileEncoding::UTF32BE),
-        [0xFF, 0xFE, _, _] => Ok(FileEncoding::UTF16LE),
-        [0xFE, 0xFF, _, _] => Ok(FileEncoding::UTF16BE),
-        _ => Ok(FileEncoding::Unknown),
+impl FileEncoding {
+    // 根据文件开头的 BOM 判断编码
+    fn from_bom(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => FileEncoding::UTF8,
+            [0xFF, 0xFE, 0x00, 0x00, ..] => FileEncoding::UTF32LE,
+            [0x00, 0x00, 0xFE, 0xFF, ..] => FileEncoding::UTF32BE,
+            [0xFF, 0xFE, ..] => FileEncoding::UTF16LE,
+            [0xFE, 0xFF, ..] => FileEncoding::UTF16BE,
+            _ => FileEncoding::Unknown,
+        }
+    }
+
+    // BOM 长度
+    fn bom_len(self) -> usize {
+        match self {
+            FileEncoding::UTF8 => 3,
+            FileEncoding::UTF16LE | FileEncoding::UTF16BE => 2,
+            FileEncoding::UTF32LE | FileEncoding::UTF32BE => 4,
+            FileEncoding::Unknown => 0,
+        }
+    }
+
+    // (码元字节数, 是否大端)，无 BOM 时按 UTF-8 处理
+    fn unit(self) -> (usize, bool) {
+        match self {
+            FileEncoding::UTF16LE => (2, false),
+            FileEncoding::UTF16BE => (2, true),
+            FileEncoding::UTF32LE => (4, false),
+            FileEncoding::UTF32BE => (4, true),
+            FileEncoding::UTF8 | FileEncoding::Unknown => (1, false),
+        }
+    }
+}
+
+/// 流式逐行读取文本文件，只保留 ASCII 字符
+///
+/// 根据 BOM 识别 UTF-8/UTF-16/UTF-32，无 BOM 时按 UTF-8 处理。
+/// 非 ASCII 字符在这几种编码中都不会产生小于 0x80 的码元，
+/// 因此逐码元过滤即可，不需要先把整个文件解码。
+pub struct AsciiLineReader<R: BufRead> {
+    inner: R,
+    encoding: FileEncoding,
+    position: u64, // 已读取的字节数
+    buf: Vec<u8>,
+}
+
+impl AsciiLineReader<BufReader<File>> {
+    pub fn open(file_path: &str) -> io::Result<Self> {
+        Self::new(BufReader::with_capacity(1 << 20, File::open(file_path)?))
     }
 }
 
+impl<R: BufRead> AsciiLineReader<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        // 读取 BOM，短文件可能一次读不满
+        let mut head = Vec::with_capacity(4);
+        while head.len() < 4 {
+            let buf = inner.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let n = buf.len().min(4 - head.len());
+            head.extend_from_slice(&buf[..n]);
+            inner.consume(n);
+        }
+
+        let encoding = FileEncoding::from_bom(&head);
+        let bom_len = encoding.bom_len();
+        let buf = head[bom_len..].to_vec();
+
+        Ok(Self {
+            inner,
+            encoding,
+            position: head.len() as u64,
+            buf,
+        })
+    }
+
+    /// 不识别 BOM，按 ASCII/UTF-8 读取
+    ///
+    /// 用于串口等数据流，连接时的噪声可能恰好与 BOM 相同。
+    pub fn utf8(inner: R) -> Self {
+        Self {
+            inner,
+            encoding: FileEncoding::Unknown,
+            position: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 已读取的字节数
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// 读取下一行，不含行尾的 "\n" 或 "\r\n"，文件结束时返回 `None`
+    pub fn next_line(&mut self) -> io::Result<Option<String>> {
+        let (width, big_endian) = self.encoding.unit();
+
+        // self.buf 中可能留有读取 BOM 时多读的字节
+        let mut start = 0;
+        loop {
+            // 已有数据中查找换行符
+            if let Some(end) = find_newline(&self.buf[start..], width, big_endian) {
+                let end = start + end;
+                let line = ascii_from_units(&self.buf[..end], width, big_endian);
+                self.buf.drain(..end + width);
+                return Ok(Some(line));
+            }
+            start = self.buf.len() - self.buf.len() % width;
+
+            let n = self.inner.read_until(b'\n', &mut self.buf)?;
+            self.position += n as u64;
+            if n == 0 {
+                break;
+            }
+
+            // 补齐换行符所在的码元，例如 UTF-16LE 的 "\n" 为 0A 00
+            let partial = self.buf.len() % width;
+            if partial != 0 {
+                let n = (&mut self.inner)
+                    .take((width - partial) as u64)
+                    .read_to_end(&mut self.buf)?;
+                self.position += n as u64;
+            }
+        }
+
+        // 文件结束，最后一行没有换行符
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let line = ascii_from_units(&self.buf, width, big_endian);
+        self.buf.clear();
+        Ok(Some(line))
+    }
+}
+
+fn unit_value(unit: &[u8], big_endian: bool) -> u32 {
+    let iter = unit.iter().map(|&b| b as u32);
+    if big_endian {
+        iter.fold(0, |v, b| (v << 8) | b)
+    } else {
+        iter.rev().fold(0, |v, b| (v << 8) | b)
+    }
+}
+
+// 查找第一个 "\n" 码元的偏移（字节）
+fn find_newline(bytes: &[u8], width: usize, big_endian: bool) -> Option<usize> {
+    bytes
+        .chunks_exact(width)
+        .position(|unit| unit_value(unit, big_endian) == b'\n' as u32)
+        .map(|i| i * width)
+}
+
+// 只保留 ASCII 码元，并去掉行尾的 "\r"
+fn ascii_from_units(bytes: &[u8], width: usize, big_endian: bool) -> String {
+    let mut line: String = bytes
+        .chunks_exact(width)
+        .map(|unit| unit_value(unit, big_endian))
+        .filter(|&v| v < 0x80) // 只保留 ASCII 字符
+        .map(|v| v as u8 as char)
+        .collect();
+    if line.ends_with('\r') {
+        line.pop();
+    }
+    line
+}
+
 /// 逐行读取文件并过滤出 ASCII 字符，流式处理
 ///
 /// # 参数
@@ -35,45 +189,44 @@ pub fn detect_encoding(file_path: &str) -> io::Result<FileEncoding> {
 /// # 返回值
 /// - `Ok(())`: 处理成功
 /// - `Err(io::Error)`: 文件读取错误
-pub fn process_ascii_lines_from_file<F>(file_path: &str, mut process_line: F) -> io::Result<()>
+pub fn process_ascii_lines_from_file<F>(file_path: &str, process_line: F) -> io::Result<()>
+where
+    F: FnMut(String) -> bool,
+{
+    process_ascii_lines_with_progress(file_path, |_, _| {}, process_line)
+}
+
+// 两次进度回调之间读取的字节数
+const PROGRESS_STEP: u64 = 4 << 20;
+
+/// 与 `process_ascii_lines_from_file` 相同，并定期回调读取进度
+///
+/// `progress` 的参数为 (已读取字节数, 文件总字节数)，处理结束时保证回调一次。
+pub fn process_ascii_lines_with_progress<F, P>(
+    file_path: &str,
+    mut progress: P,
+    mut process_line: F,
+) -> io::Result<()>
 where
     F: FnMut(String) -> bool,
+    P: FnMut(u64, u64),
 {
-    let mut file = File::open(file_path)?;
-
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
-
-    let encoding = detect_encoding(file_path)?;
-    let (decoded_str, _, _) = match encoding {
-        FileEncoding::UTF16BE => encoding_rs::Encoding::for_label(b"utf-16be")
-            .unwrap()
-            .decode(&buf),
-        FileEncoding::UTF16LE => encoding_rs::Encoding::for_label(b"utf-16le")
-            .unwrap()
-            .decode(&buf),
-        FileEncoding::UTF32BE => encoding_rs::Encoding::for_label(b"utf-32be")
-            .unwrap()
-            .decode(&buf),
-        FileEncoding::UTF32LE => encoding_rs::Encoding::for_label(b"utf-32le")
-            .unwrap()
-            .decode(&buf),
-        _ => encoding_rs::Encoding::for_label(b"utf-8")
-            .unwrap()
-            .decode(&buf),
-    };
-
-    for line in decoded_str.lines() {
-        let ascii_line: String = line
-            .chars()
-            .filter(|&c| c.is_ascii()) // 只保留 ASCII 字符
-            .collect();
+    let total = std::fs::metadata(file_path)?.len();
+    let mut reader = AsciiLineReader::open(file_path)?;
+    let mut next_report = PROGRESS_STEP;
 
+    while let Some(line) = reader.next_line()? {
         // 如果闭包返回 false，提前退出
-        if !process_line(ascii_line) {
+        if !process_line(line) {
             break;
         }
+
+        if reader.position() >= next_report {
+            progress(reader.position(), total);
+            next_report = reader.position() + PROGRESS_STEP;
+        }
     }
+    progress(reader.position(), total);
 
     Ok(())
 }